@@ -0,0 +1,98 @@
+use crate::output::Format;
+use clap::{AppSettings, Clap};
+use coins_core::{Metric, Ranking, Selection, Steps};
+
+/// Draw every step of a dump, as images or as an animation
+#[derive(Clap, Clone)]
+#[clap(setting = AppSettings::ColoredHelp)]
+pub struct Opts {
+	/// The dump to draw, as written by the server. CBOR or JSON, told apart by its contents
+	#[clap(default_value = "../server/dump.cbor")]
+	pub input: String,
+	/// png and svg write one image per step, gif, apng and webm a single animation and pdf a page
+	/// per step. webm needs the video feature. Follows the output's extension if not given, or png
+	/// without one
+	#[clap(long)]
+	pub format: Option<Format>,
+	/// The directory for png and svg, or the file for an animation or pdf. A single step can be
+	/// written to a .png or .svg file. ./images, ./svg, animation.{gif,apng,webm} or flipbook.pdf if
+	/// not given
+	#[clap(short, long)]
+	pub output: Option<String>,
+	/// The first step to draw
+	#[clap(long, default_value = "0")]
+	pub start: usize,
+	/// Draw up to but not including this step. Up to the end of the dump if not given
+	#[clap(long)]
+	pub end: Option<usize>,
+	/// Only draw this step
+	#[clap(long, conflicts_with_all = &["start", "end"])]
+	pub step: Option<usize>,
+	/// Frames per second of the animation
+	#[clap(long, default_value = "10")]
+	pub fps: u16,
+	/// Only draw every nth step. The last step is always drawn
+	#[clap(long, default_value = "1")]
+	pub stride: usize,
+	/// The width of the frames in pixels, keeping the template's shape. The template's own size
+	/// if not given. svg and pdf are always the template's size
+	#[clap(short, long)]
+	pub width: Option<u32>,
+	/// How many points are sampled along each beta distribution, half of them around its peak
+	#[clap(long, default_value = "80")]
+	pub resolution: usize,
+	/// The id or username of a player to draw. Given more than once for more players
+	#[clap(short, long, number_of_values = 1, conflicts_with = "rank")]
+	pub player: Vec<String>,
+	/// Draw the player at this rank under the metric, counting from 1. Given more than once for
+	/// more players. The best player if no players are given
+	#[clap(long, number_of_values = 1)]
+	pub rank: Vec<usize>,
+	/// Draw every player
+	#[clap(long, conflicts_with_all = &["player", "rank"])]
+	pub all: bool,
+	/// What players are ranked by: score (the most heads), proportion (of heads) or regret (the
+	/// least regret per flip)
+	#[clap(long, default_value = "proportion")]
+	pub metric: Metric,
+	/// Players with fewer flips rank below everyone with enough
+	#[clap(long, default_value = "10")]
+	pub min_flips: usize,
+	/// The SVG drawn over every frame, holding the elements the layout's anchors name. The bundled
+	/// template if not given
+	#[clap(long)]
+	pub template: Option<String>,
+	/// A TOML file placing each strategy's panels and coloring the coins. The bundled template's
+	/// layout, core/layout.toml, if not given
+	#[clap(long)]
+	pub layout: Option<String>,
+	/// How many frames to draw at once. One per core if not given
+	#[clap(short, long)]
+	pub jobs: Option<usize>,
+}
+
+impl Opts {
+	/// The steps asked for
+	pub fn steps(&self) -> Steps {
+		match self.step {
+			Some(step) => Steps {
+				start: step,
+				end: Some(step + 1),
+				stride: 1,
+			},
+			None => Steps {
+				start: self.start,
+				end: self.end,
+				stride: self.stride,
+			},
+		}
+	}
+	/// The players asked for
+	pub fn selection(&self) -> Selection {
+		let ranking = Ranking {
+			metric: self.metric,
+			min_flips: self.min_flips,
+		};
+		Selection::new(self.all, &self.player, &self.rank, ranking)
+	}
+}
@@ -1,48 +1,202 @@
+mod cli;
+mod output;
+mod pdf;
 mod utils;
-use utils::*;
+#[cfg(feature = "video")]
+mod webm;
+
+use clap::Clap;
+use cli::Opts;
+use coins_core::{Dump, FilteredData, History, Layout, RenderState};
+use indicatif::{ProgressBar, ProgressStyle};
+use output::{Format, Frame, Output};
+use rayon::prelude::*;
+use std::path::Path;
+use utils::Renderer;
+
+/// How many frames each thread draws before they are written
+const FRAMES_PER_THREAD: usize = 4;
 
 fn main() {
-    let mut opt = usvg::Options {
-        ..usvg::Options::default()
+    let opts = Opts::parse();
+    if opts.fps == 0 || opts.stride == 0 || opts.width == Some(0) {
+        exit("The frame rate, stride and width must be at least 1");
+    }
+    if opts.resolution < 2 {
+        exit("The resolution must be at least 2");
+    }
+    // an animation's format follows its file name, unless one is given
+    let format = opts
+        .format
+        .or_else(|| opts.output.as_deref().map(Format::from_path))
+        .unwrap_or(Format::Png);
+    let path = opts
+        .output
+        .as_deref()
+        .unwrap_or_else(|| format.default_output());
+    if let Some(jobs) = opts.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .expect("Could not start the threads");
+    }
+
+    let template = match &opts.template {
+        Some(path) => std::fs::read_to_string(path)
+            .unwrap_or_else(|e| exit(&format!("Could not read the template {}: {}", path, e))),
+        None => include_str!("../template.svg").to_string(),
     };
-    opt.fontdb.load_system_fonts();
+    let mut layout = opts
+        .layout
+        .as_deref()
+        .map_or_else(|| Ok(Layout::default()), Layout::read)
+        .unwrap_or_else(|e| exit(&e));
+    utils::anchor(&mut layout, &template).unwrap_or_else(|e| exit(&e));
 
-    let data = Dump::load("../server/dump.json").to_filtered();
-    let mut state = RenderState::new();
+    let dump = Dump::read(&opts.input).unwrap_or_else(|e| exit(&e));
+    let players = dump
+        .select(&opts.selection())
+        .unwrap_or_else(|e| exit(&e));
+    if players.is_empty() {
+        exit("There are no players to draw");
+    }
+    for player in &players {
+        // each player gets their own images or animation, named after them if there are several
+        let path = if players.len() > 1 {
+            player_output(path, format, username(&player.0))
+        } else {
+            path.to_string()
+        };
+        let data = dump.filter(&[player]).unwrap_or_else(|e| exit(&e));
+        draw(&opts, format, &path, &template, &layout, &data);
+    }
+}
+
+/// Draw the chosen steps of the first player in the data against the algorithms
+fn draw(
+    opts: &Opts,
+    format: Format,
+    path: &str,
+    template: &str,
+    layout: &Layout,
+    data: &FilteredData,
+) {
+    let mut state = RenderState::new(data);
+
+    let name = username(&data.players[0].0);
+    // vectors are drawn at the template's size, so there is no need to scale its image
+    let width = if format.vector() { None } else { opts.width };
+    if let (Some(max), Some(width)) = (format.max_size(), width) {
+        // checked before the renderer draws the template at that size
+        if width > max {
+            exit(&format!(
+                "Frames {} pixels wide are too big for the format, which holds up to {} a side",
+                width, max
+            ));
+        }
+    }
+    let history = History::new(data);
+    let renderer = Renderer::new(template, layout, name, width, opts.resolution, &history);
 
-    let svg_data = include_str!("../template.svg").replace(
-        "## NAME HERE ##",
-        &data.best_player_name.splitn(2, '_').collect::<Vec<&str>>()[1],
+    let steps = opts.steps().select(data.length());
+    if steps.is_empty() {
+        exit("There are no steps to draw");
+    }
+    if let Some(extension) = format.single_step() {
+        if path.ends_with(extension) && steps.len() > 1 {
+            exit(&format!(
+                "Only a single step can be written to a {} file, give a directory instead",
+                extension
+            ));
+        }
+    }
+    if let Some(max) = format.max_size() {
+        if renderer.width() > max || renderer.height() > max {
+            exit(&format!(
+                "Frames of {}x{} pixels are too big for the format, which holds up to {} a side. \
+                 Give a smaller --width",
+                renderer.width(),
+                renderer.height(),
+                max
+            ));
+        }
+    }
+    let mut output = Output::new(
+        format,
+        path,
+        renderer.width(),
+        renderer.height(),
+        steps.len(),
+        opts.fps,
     );
-    let base_tree = usvg::Tree::from_data(&svg_data.as_bytes(), &opt.to_ref()).unwrap();
-    let rtree = usvg::Tree::create(*base_tree.svg_node());
-    let pixmap_size = rtree.svg_node().size.to_screen_size();
-
-    let mut top_pixmap = tiny_skia::Pixmap::new(pixmap_size.width(), pixmap_size.height()).unwrap();
-
-    resvg::render(&base_tree, usvg::FitTo::Original, top_pixmap.as_mut()).unwrap();
-
-    let mut output_map = tiny_skia::Pixmap::new(pixmap_size.width(), pixmap_size.height()).unwrap();
-
-    // iterate for the longest number of turns taken. thompson, ucb, and naive should all have the same length
-    let total_iterations = data.thompson.len().max(data.best_player.len());
-    for i in 0..=total_iterations {
-        // update what each algorithm sees
-        state.update(&data, i);
-        // render to a clone to a tree
-        let new_tree = state.render(&rtree);
-        output_map.fill(tiny_skia::Color::WHITE);
-        resvg::render(&new_tree, usvg::FitTo::Original, output_map.as_mut()).unwrap();
-        output_map.draw_pixmap(
-            0,
-            0,
-            top_pixmap.as_ref(),
-            &tiny_skia::PixmapPaint::default(),
-            tiny_skia::Transform::identity(),
-            None,
-        );
-
-        output_map.save_png(&format!("./images/{}.png", i)).unwrap();
-        println!("{}/{}", i, total_iterations)
+    println!("Drawing {}", path);
+    let progress = ProgressBar::new(steps.len() as u64).with_style(
+        ProgressStyle::default_bar()
+            .template("{bar:40} {pos}/{len} frames, {eta} left")
+            .expect("Invalid progress bar template"),
+    );
+
+    // a batch of frames at a time, so memory stays bounded however long the game was. Each
+    // thread keeps its own renderer from one batch to the next
+    let draw_id = Renderer::next_draw();
+    let batch = rayon::current_num_threads() * FRAMES_PER_THREAD;
+    let mut next = 0;
+    for chunk in steps.chunks(batch) {
+        // update what each algorithm sees, up to each step in the batch
+        let states: Vec<(usize, RenderState)> = chunk
+            .iter()
+            .map(|&i| {
+                while next <= i {
+                    state.update(data, next);
+                    next += 1;
+                }
+                (i, state.clone())
+            })
+            .collect();
+        let frames: Vec<Frame> = states
+            .par_iter()
+            .map(|(step, state)| {
+                let frame = Renderer::with_thread(
+                    draw_id,
+                    || Renderer::new(template, layout, name, width, opts.resolution, &history),
+                    |renderer| renderer.frame(*step, state, format),
+                );
+                progress.inc(1);
+                frame
+            })
+            .collect();
+        for ((i, _), frame) in states.iter().zip(frames.iter()) {
+            output.add(*i, frame);
+        }
+    }
+    progress.finish();
+    output.finish();
+}
+
+/// Ids are a uuid and the username
+fn username(id: &str) -> &str {
+    id.split_once('_').map_or(id, |(_, name)| name)
+}
+
+/// A directory of PNGs in the output for each player, or the animation's name with the player's
+/// after it
+fn player_output(path: &str, format: Format, name: &str) -> String {
+    let path = Path::new(path);
+    match (format, path.extension()) {
+        (_, None) => path.join(name),
+        (Format::Png, Some(extension)) if extension != "png" => path.join(name),
+        (_, Some(extension)) => path.with_file_name(format!(
+            "{}_{}.{}",
+            path.file_stem().unwrap_or_default().to_string_lossy(),
+            name,
+            extension.to_string_lossy()
+        )),
     }
+    .to_string_lossy()
+    .into_owned()
+}
+
+fn exit(message: &str) -> ! {
+    println!("{}", message);
+    std::process::exit(1);
 }
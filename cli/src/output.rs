@@ -0,0 +1,213 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// How the frames are written
+#[derive(Clone, Copy)]
+pub enum Format {
+	/// One PNG per step, or a single PNG file for a single step
+	Png,
+	Gif,
+	/// An animated PNG
+	Apng,
+	/// AV1 in a WebM container, with the `video` feature
+	Webm,
+	/// One SVG per step, or a single SVG file for a single step
+	Svg,
+	/// A page per step
+	Pdf,
+}
+
+impl FromStr for Format {
+	type Err = String;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"png" => Ok(Format::Png),
+			"gif" => Ok(Format::Gif),
+			"apng" => Ok(Format::Apng),
+			"svg" => Ok(Format::Svg),
+			"pdf" => Ok(Format::Pdf),
+			"webm" if cfg!(feature = "video") => Ok(Format::Webm),
+			"webm" => Err("webm needs the video feature, cargo run --features video".to_string()),
+			_ => Err(format!(
+				"Unknown format {}, expected png, gif, apng, webm, svg or pdf",
+				s
+			)),
+		}
+	}
+}
+
+impl Format {
+	/// The format an output file's extension asks for. Anything else is a directory of PNGs. A
+	/// `.png` file is a single step, animated PNGs are written to `.apng`
+	pub fn from_path(path: &str) -> Format {
+		match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+			Some("gif") => Format::Gif,
+			Some("png") => Format::Png,
+			Some("apng") => Format::Apng,
+			Some("webm") => Format::Webm,
+			Some("svg") => Format::Svg,
+			Some("pdf") => Format::Pdf,
+			_ => Format::Png,
+		}
+	}
+	/// Where the frames go when no output is given
+	pub fn default_output(&self) -> &'static str {
+		match self {
+			Format::Png => "./images",
+			Format::Gif => "animation.gif",
+			Format::Apng => "animation.apng",
+			Format::Webm => "animation.webm",
+			Format::Svg => "./svg",
+			Format::Pdf => "flipbook.pdf",
+		}
+	}
+	/// Whether frames are drawn as vectors instead of pixels
+	pub fn vector(&self) -> bool {
+		matches!(self, Format::Svg | Format::Pdf)
+	}
+	/// The extension of a file holding a single step, for the formats that write one file a step
+	pub fn single_step(&self) -> Option<&'static str> {
+		match self {
+			Format::Png => Some(".png"),
+			Format::Svg => Some(".svg"),
+			_ => None,
+		}
+	}
+	/// The widest and tallest frame the format can hold, in pixels
+	pub fn max_size(&self) -> Option<u32> {
+		match self {
+			Format::Gif => Some(u16::MAX as u32),
+			_ => None,
+		}
+	}
+}
+
+/// A drawn frame, in whatever the format needs
+pub enum Frame {
+	Pixels(tiny_skia::Pixmap),
+	Svg(String),
+	Pdf(crate::pdf::Page),
+}
+
+/// Where the frames go, one at a time and in order
+pub enum Output {
+	/// A directory of PNGs named by their step, or a file for a single step
+	Png(PathBuf),
+	Gif {
+		encoder: gif::Encoder<BufWriter<File>>,
+		/// How long each frame shows, in hundredths of a second
+		delay: u16,
+	},
+	Apng(png::Writer<BufWriter<File>>),
+	/// Boxed, since the encoder is much larger than the others
+	#[cfg(feature = "video")]
+	Webm(Box<crate::webm::Webm>),
+	/// A directory of SVGs named by their step, or a file for a single step
+	Svg(PathBuf),
+	Pdf(crate::pdf::Flipbook),
+}
+
+impl Output {
+	/// Start writing `frames` frames of the given size, shown `fps` to a second
+	pub fn new(format: Format, path: &str, width: u32, height: u32, frames: usize, fps: u16) -> Output {
+		let create = || BufWriter::new(File::create(path).expect("Could not open output file"));
+		match format {
+			Format::Png if path.ends_with(".png") => Output::Png(PathBuf::from(path)),
+			Format::Png => {
+				std::fs::create_dir_all(path).expect("Could not create the output directory");
+				Output::Png(PathBuf::from(path))
+			}
+			Format::Gif => {
+				let mut encoder = gif::Encoder::new(create(), width as u16, height as u16, &[])
+					.expect("Could not write the GIF");
+				encoder
+					.set_repeat(gif::Repeat::Infinite)
+					.expect("Could not write the GIF");
+				Output::Gif {
+					encoder,
+					delay: (100.0 / fps as f64).round() as u16,
+				}
+			}
+			Format::Apng => {
+				let mut encoder = png::Encoder::new(create(), width, height);
+				encoder.set_color(png::ColorType::Rgba);
+				encoder.set_depth(png::BitDepth::Eight);
+				// 0 plays forever
+				encoder
+					.set_animated(frames as u32, 0)
+					.expect("Could not write the APNG");
+				encoder
+					.set_frame_delay(1, fps)
+					.expect("Could not write the APNG");
+				Output::Apng(encoder.write_header().expect("Could not write the APNG"))
+			}
+			#[cfg(feature = "video")]
+			Format::Webm => Output::Webm(Box::new(crate::webm::Webm::new(path, width, height, fps))),
+			Format::Svg if path.ends_with(".svg") => Output::Svg(PathBuf::from(path)),
+			Format::Svg => {
+				std::fs::create_dir_all(path).expect("Could not create the output directory");
+				Output::Svg(PathBuf::from(path))
+			}
+			Format::Pdf => Output::Pdf(crate::pdf::Flipbook::new(path)),
+			#[cfg(not(feature = "video"))]
+			Format::Webm => unreachable!("webm is only parsed with the video feature"),
+		}
+	}
+	/// Write the frame for the given step
+	pub fn add(&mut self, step: usize, frame: &Frame) {
+		let (output, frame) = match (self, frame) {
+			(Output::Svg(path), Frame::Svg(svg)) => {
+				let file = match path.is_dir() {
+					true => path.join(format!("{}.svg", step)),
+					false => path.clone(),
+				};
+				std::fs::write(file, svg).expect("Could not write the SVG");
+				return;
+			}
+			(Output::Pdf(flipbook), Frame::Pdf(page)) => return flipbook.add(page),
+			(output, Frame::Pixels(pixels)) => (output, pixels),
+			_ => unreachable!("frames are drawn for the output's format"),
+		};
+		// frames are drawn on white, so the premultiplied pixels are the same as straight ones
+		match output {
+			Output::Png(path) => {
+				let file = match path.is_dir() {
+					true => path.join(format!("{}.png", step)),
+					false => path.clone(),
+				};
+				frame.save_png(file).expect("Could not write the PNG")
+			}
+			Output::Gif { encoder, delay } => {
+				let mut pixels = frame.data().to_vec();
+				let mut gif_frame = gif::Frame::from_rgba_speed(
+					frame.width() as u16,
+					frame.height() as u16,
+					&mut pixels,
+					10,
+				);
+				gif_frame.delay = *delay;
+				encoder
+					.write_frame(&gif_frame)
+					.expect("Could not write the GIF");
+			}
+			Output::Apng(writer) => writer
+				.write_image_data(frame.data())
+				.expect("Could not write the APNG"),
+			#[cfg(feature = "video")]
+			Output::Webm(webm) => webm.add(frame),
+			Output::Svg(_) | Output::Pdf(_) => unreachable!("vector frames are written above"),
+		}
+	}
+	/// Finish the animation once every frame is added
+	pub fn finish(self) {
+		match self {
+			Output::Png(_) | Output::Gif { .. } | Output::Svg(_) => {}
+			Output::Pdf(flipbook) => flipbook.finish(),
+			Output::Apng(writer) => writer.finish().expect("Could not write the APNG"),
+			#[cfg(feature = "video")]
+			Output::Webm(webm) => webm.finish(),
+		}
+	}
+}
@@ -0,0 +1,225 @@
+//! Writing frames as pages of a PDF, drawn as vectors from the same tree the images are rasterised
+//! from. Only what the templates use is written: paths filled or stroked with plain colors, inside
+//! groups with transforms and opacity
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref};
+use usvg::{NodeKind, PathSegment};
+
+/// Points in a CSS pixel, which usvg measures sizes in
+const POINTS_PER_PIXEL: f64 = 0.75;
+
+/// One frame's drawing, made on whichever thread drew the frame
+pub struct Page {
+	/// The size of the page in points
+	size: (f32, f32),
+	content: Vec<u8>,
+	/// The fill and stroke opacity of each graphics state the drawing uses, named `G0`, `G1`, ...
+	alphas: Vec<(f32, f32)>,
+}
+
+impl Page {
+	pub fn new(tree: &usvg::Tree) -> Page {
+		let svg = tree.svg_node();
+		let (width, height) = (svg.size.width(), svg.size.height());
+		let view_box = svg.view_box.rect;
+		let scale_x = width / view_box.width() * POINTS_PER_PIXEL;
+		let scale_y = height / view_box.height() * POINTS_PER_PIXEL;
+
+		let mut page = Page {
+			size: (
+				(width * POINTS_PER_PIXEL) as f32,
+				(height * POINTS_PER_PIXEL) as f32,
+			),
+			content: Vec::new(),
+			alphas: Vec::new(),
+		};
+		let mut content = Content::new();
+		// PDF measures up from the bottom of the page, SVG down from the top
+		content.transform([
+			scale_x as f32,
+			0.0,
+			0.0,
+			-scale_y as f32,
+			(-view_box.x() * scale_x) as f32,
+			(height * POINTS_PER_PIXEL + view_box.y() * scale_y) as f32,
+		]);
+		for child in tree.root().children() {
+			page.draw(&mut content, &child, 1.0);
+		}
+		page.content = content.finish();
+		page
+	}
+	fn draw(&mut self, content: &mut Content, node: &usvg::Node, opacity: f64) {
+		match &*node.borrow() {
+			NodeKind::Group(group) => {
+				content.save_state();
+				content.transform(matrix(&group.transform));
+				for child in node.children() {
+					self.draw(content, &child, opacity * group.opacity.value());
+				}
+				content.restore_state();
+			}
+			NodeKind::Path(path) if path.visibility == usvg::Visibility::Visible => {
+				let fill = path.fill.as_ref().and_then(|fill| match &fill.paint {
+					usvg::Paint::Color(color) => Some((color, fill.opacity.value(), fill.rule)),
+					// gradients and patterns are not used by the templates
+					_ => None,
+				});
+				let stroke = path.stroke.as_ref().and_then(|stroke| match &stroke.paint {
+					usvg::Paint::Color(color) => Some((color, stroke)),
+					_ => None,
+				});
+				if fill.is_none() && stroke.is_none() {
+					return;
+				}
+				content.save_state();
+				content.transform(matrix(&path.transform));
+				let fill_alpha = fill.map_or(1.0, |(_, alpha, _)| alpha) * opacity;
+				let stroke_alpha = stroke.map_or(1.0, |(_, stroke)| stroke.opacity.value()) * opacity;
+				if fill_alpha < 1.0 || stroke_alpha < 1.0 {
+					let name = self.alpha(fill_alpha as f32, stroke_alpha as f32);
+					content.set_parameters(Name(name.as_bytes()));
+				}
+				if let Some((color, _, _)) = fill {
+					let (r, g, b) = rgb(color);
+					content.set_fill_rgb(r, g, b);
+				}
+				if let Some((color, stroke)) = stroke {
+					let (r, g, b) = rgb(color);
+					content.set_stroke_rgb(r, g, b);
+					content.set_line_width(stroke.width.value() as f32);
+					content.set_miter_limit(stroke.miterlimit.value() as f32);
+					content.set_line_cap(match stroke.linecap {
+						usvg::LineCap::Butt => pdf_writer::types::LineCapStyle::ButtCap,
+						usvg::LineCap::Round => pdf_writer::types::LineCapStyle::RoundCap,
+						usvg::LineCap::Square => pdf_writer::types::LineCapStyle::ProjectingSquareCap,
+					});
+					content.set_line_join(match stroke.linejoin {
+						usvg::LineJoin::Miter => pdf_writer::types::LineJoinStyle::MiterJoin,
+						usvg::LineJoin::Round => pdf_writer::types::LineJoinStyle::RoundJoin,
+						usvg::LineJoin::Bevel => pdf_writer::types::LineJoinStyle::BevelJoin,
+					});
+					if let Some(dashes) = &stroke.dasharray {
+						content.set_dash_pattern(dashes.iter().map(|d| *d as f32), stroke.dashoffset);
+					}
+				}
+				for segment in path.data.iter() {
+					match *segment {
+						PathSegment::MoveTo { x, y } => content.move_to(x as f32, y as f32),
+						PathSegment::LineTo { x, y } => content.line_to(x as f32, y as f32),
+						PathSegment::CurveTo {
+							x1,
+							y1,
+							x2,
+							y2,
+							x,
+							y,
+						} => content.cubic_to(
+							x1 as f32, y1 as f32, x2 as f32, y2 as f32, x as f32, y as f32,
+						),
+						PathSegment::ClosePath => content.close_path(),
+					};
+				}
+				match (fill, stroke.is_some()) {
+					(Some((_, _, usvg::FillRule::NonZero)), true) => content.fill_nonzero_and_stroke(),
+					(Some((_, _, usvg::FillRule::EvenOdd)), true) => content.fill_even_odd_and_stroke(),
+					(Some((_, _, usvg::FillRule::NonZero)), false) => content.fill_nonzero(),
+					(Some((_, _, usvg::FillRule::EvenOdd)), false) => content.fill_even_odd(),
+					(None, _) => content.stroke(),
+				};
+				content.restore_state();
+			}
+			_ => {}
+		}
+	}
+	/// The name of a graphics state with the given opacity, added if it is new
+	fn alpha(&mut self, fill: f32, stroke: f32) -> String {
+		let index = match self.alphas.iter().position(|alpha| *alpha == (fill, stroke)) {
+			Some(index) => index,
+			None => {
+				self.alphas.push((fill, stroke));
+				self.alphas.len() - 1
+			}
+		};
+		format!("G{}", index)
+	}
+}
+
+/// Pages are written as they come, and the page tree once they all have
+pub struct Flipbook {
+	path: String,
+	pdf: Pdf,
+	pages: Vec<Ref>,
+	next: i32,
+}
+
+const CATALOG: Ref = Ref::new(1);
+const PAGE_TREE: Ref = Ref::new(2);
+
+impl Flipbook {
+	pub fn new(path: &str) -> Flipbook {
+		Flipbook {
+			path: path.to_string(),
+			pdf: Pdf::new(),
+			pages: Vec::new(),
+			next: 3,
+		}
+	}
+	fn reference(&mut self) -> Ref {
+		self.next += 1;
+		Ref::new(self.next - 1)
+	}
+	pub fn add(&mut self, page: &Page) {
+		let id = self.reference();
+		let content = self.reference();
+		let states: Vec<Ref> = page.alphas.iter().map(|_| self.reference()).collect();
+		for (state, (fill, stroke)) in states.iter().zip(page.alphas.iter()) {
+			self.pdf
+				.ext_graphics(*state)
+				.non_stroking_alpha(*fill)
+				.stroking_alpha(*stroke);
+		}
+		self.pdf.stream(content, &page.content);
+		let mut pdf_page = self.pdf.page(id);
+		pdf_page
+			.parent(PAGE_TREE)
+			.media_box(Rect::new(0.0, 0.0, page.size.0, page.size.1))
+			.contents(content);
+		let mut resources = pdf_page.resources();
+		let mut names = resources.ext_g_states();
+		for (i, state) in states.iter().enumerate() {
+			names.pair(Name(format!("G{}", i).as_bytes()), *state);
+		}
+		names.finish();
+		resources.finish();
+		pdf_page.finish();
+		self.pages.push(id);
+	}
+	pub fn finish(mut self) {
+		self.pdf.catalog(CATALOG).pages(PAGE_TREE);
+		let count = self.pages.len() as i32;
+		self.pdf
+			.pages(PAGE_TREE)
+			.kids(self.pages.iter().copied())
+			.count(count);
+		std::fs::write(&self.path, self.pdf.finish()).expect("Could not write the PDF");
+	}
+}
+
+fn matrix(transform: &usvg::Transform) -> [f32; 6] {
+	[
+		transform.a as f32,
+		transform.b as f32,
+		transform.c as f32,
+		transform.d as f32,
+		transform.e as f32,
+		transform.f as f32,
+	]
+}
+
+fn rgb(color: &usvg::Color) -> (f32, f32, f32) {
+	(
+		color.red as f32 / 255.0,
+		color.green as f32 / 255.0,
+		color.blue as f32 / 255.0,
+	)
+}
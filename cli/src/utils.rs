@@ -1,243 +1,255 @@
-use serde::Deserialize;
+use coins_core::{
+	charts, naive_bars, naive_status, Align, Chart, History, Kind, Label, Layout, NaiveMeanState,
+	Panel, Rectangle, RenderState, Strategy, ThompsonBetaState, UcbCountState,
+};
+use coins_core::render::{beta_pdf, beta_samples};
+use crate::output::{Format, Frame};
+use crate::pdf::Page;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use usvg::NodeExt;
 use usvg::PathSegment;
 
-#[derive(Deserialize)]
-pub struct Dump {
-	algorithms: Vec<(String, Vec<(usize, bool)>)>,
-	players: Vec<(String, Vec<(usize, bool)>)>,
-}
+/// Tells draws apart, so that a thread only reuses its renderer for the draw it was made for
+static DRAWS: AtomicUsize = AtomicUsize::new(0);
 
-impl Dump {
-	pub fn load(path: &str) -> Self {
-		let file = std::fs::File::open(path).unwrap();
-		let reader = std::io::BufReader::new(file);
-		let dump: Dump = serde_cbor::from_reader(reader).unwrap();
-		dump
-	}
-	pub fn to_filtered(&self) -> FilteredData {
-		let mut filtered = FilteredData::new();
-		for (algorithm, data) in &self.algorithms {
-			match algorithm.as_str() {
-				"Thompson Strategy" => {
-					filtered.thompson = data.clone();
-				}
-				"UCB Strategy" => {
-					filtered.ucb = data.clone();
-				}
-				"Naive Strategy" => {
-					filtered.naive = data.clone();
-				}
-				_ => (),
-			}
-		}
-		let temp_vec = Vec::new();
-		let (name, _, past) = self.players.iter().fold(
-			("", 0.0, &temp_vec),
-			|(acc_name, acc_proportion, acc_past), (name, past)| {
-				let (flips, heads) =
-					past.iter()
-						.fold((0, 0), |(a_flips, a_heads), (_, success)| {
-							(a_flips + 1, if *success { a_heads + 1 } else { a_heads })
-						});
-				if heads as f64 / flips as f64 > acc_proportion {
-					(&name, heads as f64 / flips as f64, &past)
-				} else {
-					(acc_name, acc_proportion, acc_past)
-				}
-			},
-		);
-		filtered.best_player_name = name.to_string();
-		filtered.best_player = past.clone();
-		filtered
-	}
+thread_local! {
+	/// This thread's renderer and the draw it was made for
+	static RENDERER: RefCell<Option<(usize, Renderer)>> = const { RefCell::new(None) };
 }
 
-pub struct FilteredData {
-	pub thompson: Vec<(usize, bool)>,
-	pub naive: Vec<(usize, bool)>,
-	pub ucb: Vec<(usize, bool)>,
-	pub best_player: Vec<(usize, bool)>,
-	pub best_player_name: String,
+/// Everything needed to draw frames. Trees cannot be shared between threads, so each thread
+/// makes its own once per draw, see [`Renderer::with_thread`]
+pub struct Renderer {
+	opt: usvg::Options,
+	/// The size of the template, with nothing in it
+	tree: usvg::Tree,
+	/// How the template is scaled to the frames
+	fit: usvg::FitTo,
+	/// The template, drawn over every frame
+	top: tiny_skia::Pixmap,
+	/// The template, copied over every vector frame
+	template: usvg::Tree,
+	/// Where the strategies are drawn, with its anchors placed
+	layout: Layout,
+	/// The username of the player being drawn
+	name: String,
+	/// How many points are sampled along each beta distribution
+	resolution: usize,
+	/// The time series of the session, with a cursor drawn at each frame's step
+	charts: Vec<Chart>,
 }
 
-impl FilteredData {
-	fn new() -> Self {
-		Self {
-			thompson: Vec::new(),
-			naive: Vec::new(),
-			ucb: Vec::new(),
-			best_player: Vec::new(),
-			best_player_name: String::new(),
+impl Renderer {
+	/// Frames are `width` pixels wide, or the template's own size if not given. They grow to fit
+	/// any charts placed past the template
+	pub fn new(
+		svg_data: &str,
+		layout: &Layout,
+		name: &str,
+		width: Option<u32>,
+		resolution: usize,
+		history: &History,
+	) -> Self {
+		let mut opt = usvg::Options {
+			..usvg::Options::default()
+		};
+		opt.fontdb.load_system_fonts();
+		let base_tree = usvg::Tree::from_data(svg_data.as_bytes(), &opt.to_ref()).unwrap();
+		let charts = charts(history, &layout.panels, 0, |coin| layout.color(coin));
+		let tree = usvg::Tree::create(canvas(*base_tree.svg_node(), &charts));
+		let fit = width.map_or(usvg::FitTo::Original, usvg::FitTo::Width);
+		let size = fit.fit_to(tree.svg_node().size.to_screen_size()).unwrap();
+		let mut top = tiny_skia::Pixmap::new(size.width(), size.height()).unwrap();
+		// the template at the frames' scale, in their top left corner
+		let zoom = size.width() as f32 / tree.svg_node().size.width() as f32;
+		resvg::render(&base_tree, usvg::FitTo::Zoom(zoom), top.as_mut()).unwrap();
+		Renderer {
+			opt,
+			tree,
+			fit,
+			top,
+			template: base_tree,
+			layout: layout.clone(),
+			name: name.to_string(),
+			resolution,
+			charts,
 		}
 	}
-}
-
-pub struct GeneralState {
-	/// Count for each coin
-	count: Vec<usize>,
-	successes: usize,
-	failures: usize,
-}
-
-impl GeneralState {
-	fn new() -> Self {
-		Self {
-			count: vec![0; 3],
-			successes: 0,
-			failures: 0,
+	pub fn width(&self) -> u32 {
+		self.top.width()
+	}
+	pub fn height(&self) -> u32 {
+		self.top.height()
+	}
+	/// Draw the state at the step on white, under the template
+	pub fn draw(&self, step: usize, state: &RenderState) -> tiny_skia::Pixmap {
+		let new_tree = render(state, step, &self.tree, self);
+		let mut pixmap = tiny_skia::Pixmap::new(self.width(), self.height()).unwrap();
+		pixmap.fill(tiny_skia::Color::WHITE);
+		resvg::render(&new_tree, self.fit, pixmap.as_mut()).unwrap();
+		pixmap.draw_pixmap(
+			0,
+			0,
+			self.top.as_ref(),
+			&tiny_skia::PixmapPaint::default(),
+			tiny_skia::Transform::identity(),
+			None,
+		);
+		pixmap
+	}
+	/// Draw the state at the step as a whole tree, with the template over it
+	pub fn tree(&self, step: usize, state: &RenderState) -> usvg::Tree {
+		let tree = render(state, step, &self.tree, self);
+		for mut child in self.template.root().children() {
+			let defs = matches!(*child.borrow(), usvg::NodeKind::Defs);
+			if defs {
+				// gradients and clips are kept with the definitions, so they can still be found
+				for mut def in child.children() {
+					tree.defs().append(def.make_deep_copy());
+				}
+			} else {
+				tree.root().append(child.make_deep_copy());
+			}
 		}
+		tree
 	}
-}
-
-pub struct ThompsonBetaState {
-	a: Vec<usize>,
-	b: Vec<usize>,
-}
-
-impl ThompsonBetaState {
-	fn new() -> Self {
-		Self {
-			a: vec![1; 3],
-			b: vec![1; 3],
+	/// A new draw, for [`Renderer::with_thread`]
+	pub fn next_draw() -> usize {
+		DRAWS.fetch_add(1, Ordering::Relaxed)
+	}
+	/// Run `f` with this thread's renderer for the draw, made by `make` the first time the thread
+	/// draws one of its frames. Making one loads the system fonts and draws the template, so it
+	/// is kept for every later batch of the same draw
+	pub fn with_thread<T>(
+		draw: usize,
+		make: impl FnOnce() -> Renderer,
+		f: impl FnOnce(&Renderer) -> T,
+	) -> T {
+		RENDERER.with(|renderer| {
+			let mut renderer = renderer.borrow_mut();
+			if !matches!(&*renderer, Some((made_for, _)) if *made_for == draw) {
+				*renderer = Some((draw, make()));
+			}
+			let (_, renderer) = renderer.as_ref().expect("The renderer was just made");
+			f(renderer)
+		})
+	}
+	/// Draw the state at the step as the format needs it. Vectors are the template's own size
+	pub fn frame(&self, step: usize, state: &RenderState, format: Format) -> Frame {
+		match format {
+			Format::Svg => {
+				Frame::Svg(self.tree(step, state).to_string(&usvg::XmlOptions::default()))
+			}
+			Format::Pdf => Frame::Pdf(Page::new(&self.tree(step, state))),
+			_ => Frame::Pixels(self.draw(step, state)),
 		}
 	}
 }
 
-pub struct UcbCountState {
-	past: Vec<(usize, usize)>,
-	total_flips: usize,
-}
-
-impl UcbCountState {
-	fn new() -> Self {
-		UcbCountState {
-			past: vec![(0, 0); 3],
-			total_flips: 0,
-		}
+/// The template's size, grown right and down to fit the charts placed past it
+fn canvas(svg: usvg::Svg, charts: &[Chart]) -> usvg::Svg {
+	let rect = svg.view_box.rect;
+	let (right, bottom) = charts
+		.iter()
+		.map(Chart::extent)
+		.fold((rect.right(), rect.bottom()), |(right, bottom), (x, y)| {
+			(right.max(x), bottom.max(y))
+		});
+	let (width, height) = (right - rect.x(), bottom - rect.y());
+	let scale = svg.size.width() / rect.width();
+	usvg::Svg {
+		size: usvg::Size::new(width * scale, height * scale).unwrap(),
+		view_box: usvg::ViewBox {
+			rect: usvg::Rect::new(rect.x(), rect.y(), width, height).unwrap(),
+			aspect: svg.view_box.aspect,
+		},
 	}
 }
 
-pub struct RenderState {
-	thompson: (GeneralState, ThompsonBetaState),
-	ucb: (GeneralState, UcbCountState),
-	naive: GeneralState,
-	player: GeneralState,
+/// Place the layout's anchored panels over the elements of the template with those ids
+pub fn anchor(layout: &mut Layout, svg_data: &str) -> Result<(), String> {
+	let tree = usvg::Tree::from_data(svg_data.as_bytes(), &usvg::Options::default().to_ref())
+		.map_err(|e| format!("Could not read the template: {}", e))?;
+	layout.anchor(|id| {
+		let bbox = tree.node_by_id(id)?.calculate_bbox()?;
+		Some(Rectangle {
+			x: bbox.x(),
+			y: bbox.y(),
+			width: bbox.width(),
+			height: bbox.height(),
+		})
+	})
 }
 
-impl RenderState {
-	pub fn new() -> Self {
-		Self {
-			thompson: (GeneralState::new(), ThompsonBetaState::new()),
-			ucb: (GeneralState::new(), UcbCountState::new()),
-			naive: GeneralState::new(),
-			player: GeneralState::new(),
-		}
-	}
-	pub fn update(&mut self, data: &FilteredData, index: usize) {
-		if let Some((coin, result)) = data.thompson.get(index) {
-			self.thompson.0.count[*coin] += 1;
-			self.thompson.0.successes += if *result { 1 } else { 0 };
-			self.thompson.0.failures += if *result { 0 } else { 1 };
-			self.thompson.1.a[*coin] += if *result { 1 } else { 0 };
-			self.thompson.1.b[*coin] += if *result { 0 } else { 1 };
-		};
-		if let Some((coin, result)) = data.naive.get(index) {
-			self.naive.count[*coin] += 1;
-			self.naive.successes += if *result { 1 } else { 0 };
-			self.naive.failures += if *result { 0 } else { 1 };
+/// Draw the given state's panels, with its first player, and the charts' cursors at the step on
+/// top of a copy of the base tree
+fn render(
+	state: &RenderState,
+	step: usize,
+	base_svg: &usvg::Tree,
+	renderer: &Renderer,
+) -> usvg::Tree {
+	let layout = &renderer.layout;
+	let svg = usvg::Tree::create(*base_svg.svg_node());
+	svg.root().clone_from(&base_svg.root().make_deep_copy());
+
+	let mut text = Vec::new();
+	for panel in &layout.panels {
+		let general = match panel.strategy {
+			Strategy::Thompson => &state.thompson.0,
+			Strategy::Naive => &state.naive.0,
+			Strategy::Ucb => &state.ucb.0,
+			Strategy::Player => &state.players[0],
 		};
-		if let Some((coin, result)) = data.ucb.get(index) {
-			self.ucb.0.count[*coin] += 1;
-			self.ucb.1.total_flips += 1;
-			if *result {
-				self.ucb.0.successes += 1;
-				self.ucb.1.past[*coin].0 += 1;
-			} else {
-				self.ucb.0.failures += 1;
-				self.ucb.1.past[*coin].1 += 1;
+		match panel.kind {
+			Kind::Boxes => render_boxes(
+				&svg,
+				&panel.rect(),
+				&general.count,
+				general.failures + general.successes,
+				layout,
+			),
+			Kind::Curves => render_thompson(
+				&svg,
+				panel,
+				&state.thompson.1,
+				layout,
+				renderer.resolution,
+			),
+			Kind::Intervals => render_ucb(&svg, &panel.rect(), &state.ucb.1, layout),
+			Kind::Means => render_naive(&svg, &panel.rect(), &state.naive.1, layout),
+			Kind::Count => text.push(panel.label(format!(
+				"{}/{}",
+				general.successes,
+				general.successes + general.failures
+			))),
+			Kind::Status => {
+				let naive = &state.naive.1;
+				let explored = (naive.flips.min(naive.exploration), naive.exploration);
+				text.push(panel.label(naive_status(explored, naive.committed)));
 			}
-		};
-		if let Some((coin, result)) = data.best_player.get(index) {
-			self.player.count[*coin] += 1;
-			self.player.successes += if *result { 1 } else { 0 };
-			self.player.failures += if *result { 0 } else { 1 };
-		};
+			Kind::Name => text.push(panel.label(renderer.name.clone())),
+			// drawn together, once every panel sharing a chart is known
+			Kind::Heads | Kind::Best | Kind::Estimates => {}
+		}
 	}
-	pub fn render(&self, base_svg: &usvg::Tree) -> usvg::Tree {
-		let svg = usvg::Tree::create(*base_svg.svg_node());
-		svg.root().clone_from(&base_svg.root().make_deep_copy());
-		render_thompson(&svg, &self.thompson.1);
-		render_ucb(&svg, &self.ucb.1);
-
-		render_boxes(
-			&svg,
-			0,
-			self.thompson.0.count[0],
-			self.thompson.0.count[1],
-			self.thompson.0.count[2],
-			self.thompson.0.failures + self.thompson.0.successes,
-		);
-		render_boxes(
-			&svg,
-			1,
-			self.naive.count[0],
-			self.naive.count[1],
-			self.naive.count[2],
-			self.naive.failures + self.naive.successes,
-		);
-		render_boxes(
-			&svg,
-			2,
-			self.ucb.0.count[0],
-			self.ucb.0.count[1],
-			self.ucb.0.count[2],
-			self.ucb.0.failures + self.ucb.0.successes,
-		);
-		render_boxes(
-			&svg,
-			3,
-			self.player.count[0],
-			self.player.count[1],
-			self.player.count[2],
-			self.player.failures + self.player.successes,
-		);
-
-		render_text(
-			&svg,
-			(self.thompson.0.successes, self.thompson.0.failures),
-			(self.naive.successes, self.naive.failures),
-			(self.ucb.0.successes, self.ucb.0.failures),
-			(self.player.successes, self.player.failures),
-		);
-		svg
+	for chart in &renderer.charts {
+		render_chart(&svg, chart, step);
+		text.extend(chart.labels());
 	}
+	render_text(&svg, &renderer.opt, &text);
+	svg
 }
 
+/// Stack one box per coin down the panel, each as tall as the proportion of flips on that coin
 fn render_boxes(
 	tree: &usvg::Tree,
-	index: usize,
-	count1: usize,
-	count2: usize,
-	count3: usize,
+	panel: &Rectangle,
+	counts: &[usize],
 	total: usize,
+	layout: &Layout,
 ) {
-	let base_height = 37.041668;
-	let width = 26.458332;
-	let scale_height = 142.874995 - base_height;
-	let base_width = match index {
-		0 => 26.458332,
-		1 => 68.791664,
-		2 => 111.125,
-		3 => 153.458332,
-		_ => panic!("Invalid index"),
-	};
-	let (p1, p2, p3) = (
-		count1 as f64 / total as f64,
-		count2 as f64 / total as f64,
-		count3 as f64 / total as f64,
-	);
-
 	fn append(tree: &usvg::Tree, x: f64, y: f64, width: f64, height: f64, fill: usvg::Fill) {
 		tree.root().append_kind(usvg::NodeKind::Path(usvg::Path {
 			data: std::rc::Rc::new(usvg::PathData(vec![
@@ -261,51 +273,99 @@ fn render_boxes(
 		}));
 	}
 
-	append(
-		tree,
-		base_width,
-		base_height + 0.0,
-		width,
-		scale_height * p1,
-		usvg::Fill::from_paint(usvg::Paint::Color(usvg::Color::new_rgb(0, 157, 255))),
-	);
-	append(
-		tree,
-		base_width,
-		base_height + scale_height * p1,
-		width,
-		scale_height * p2,
-		usvg::Fill::from_paint(usvg::Paint::Color(usvg::Color::new_rgb(255, 95, 89))),
-	);
-	append(
-		tree,
-		base_width,
-		base_height + scale_height * (p1 + p2),
-		width,
-		scale_height * p3,
-		usvg::Fill::from_paint(usvg::Paint::Color(usvg::Color::new_rgb(0, 176, 89))),
-	);
+	let mut y = panel.y;
+	for (coin, count) in counts.iter().enumerate() {
+		let height = panel.height * (*count as f64 / total as f64);
+		append(
+			tree,
+			panel.x,
+			y,
+			panel.width,
+			height,
+			usvg::Fill::from_paint(coin_paint(layout, coin)),
+		);
+		y += height;
+	}
 }
 
-fn render_thompson(tree: &usvg::Tree, thompson: &ThompsonBetaState) {
-	let (a1, a2, a3) = (thompson.a[0], thompson.a[1], thompson.a[2]);
-	let (b1, b2, b3) = (thompson.b[0], thompson.b[1], thompson.b[2]);
-	fn append(tree: &usvg::Tree, a: f64, b: f64, stroke_color: usvg::Paint) {
-		use rv::prelude::ContinuousDistr;
-		let dist = rv::dist::Beta::new(a, b).unwrap();
-		let mut path = vec![PathSegment::MoveTo {
-			x: 0.001,
-			y: dist.pdf(&0.001),
-		}];
-		path.append(
-			&mut (1..20)
-				.map(|i| {
-					let x = i as f64 / 20.0;
-					let y = dist.pdf(&x);
+/// The paint the layout gives the coin
+fn coin_paint(layout: &Layout, coin: usize) -> usvg::Paint {
+	let (r, g, b) = layout.color(coin);
+	usvg::Paint::Color(usvg::Color::new_rgb(r, g, b))
+}
+
+/// A bar per coin for the proportion of heads naive saw while exploring. Once it settles, the coin
+/// it settled on is outlined and the others fade
+fn render_naive(tree: &usvg::Tree, panel: &Rectangle, naive: &NaiveMeanState, layout: &Layout) {
+	for (coin, bar) in naive_bars(panel, &naive.means()).iter().enumerate() {
+		let chosen = naive.committed == Some(coin);
+		let (x, y) = (bar.x, bar.y);
+		tree.root().append_kind(usvg::NodeKind::Path(usvg::Path {
+			data: std::rc::Rc::new(usvg::PathData(vec![
+				PathSegment::MoveTo { x, y },
+				PathSegment::LineTo {
+					x: x + bar.width,
+					y,
+				},
+				PathSegment::LineTo {
+					x: x + bar.width,
+					y: y + bar.height,
+				},
+				PathSegment::LineTo {
+					x,
+					y: y + bar.height,
+				},
+				PathSegment::ClosePath,
+			])),
+			stroke: chosen.then(|| usvg::Stroke {
+				paint: usvg::Paint::Color(usvg::Color::black()),
+				width: usvg::StrokeWidth::new(0.8),
+				..usvg::Stroke::default()
+			}),
+			fill: Some(usvg::Fill {
+				paint: coin_paint(layout, coin),
+				opacity: usvg::Opacity::new(if naive.committed.is_some() && !chosen {
+					0.35
+				} else {
+					1.0
+				}),
+				..usvg::Fill::default()
+			}),
+			..usvg::Path::default()
+		}));
+	}
+}
+
+/// The beta distribution of each coin across the panel, up to the panel's peak density at its top
+fn render_thompson(
+	tree: &usvg::Tree,
+	panel: &Panel,
+	thompson: &ThompsonBetaState,
+	layout: &Layout,
+	resolution: usize,
+) {
+	let bottom = panel.y + panel.height;
+	let transform = usvg::Transform::new(
+		panel.width,
+		0.0,
+		0.0,
+		-panel.height / panel.peak,
+		panel.x,
+		bottom,
+	);
+	let append = |a: f64, b: f64, stroke_color: usvg::Paint| {
+		let mut path = beta_samples(a, b, resolution)
+			.into_iter()
+			.enumerate()
+			.map(|(i, x)| {
+				let y = beta_pdf(a, b, x);
+				if i == 0 {
+					PathSegment::MoveTo { x, y }
+				} else {
 					PathSegment::LineTo { x, y }
-				})
-				.collect::<Vec<PathSegment>>(),
-		);
+				}
+			})
+			.collect::<Vec<PathSegment>>();
 		path.push(PathSegment::MoveTo { x: 1.0, y: 0.0 });
 		path.push(PathSegment::ClosePath);
 		tree.root().append_kind(usvg::NodeKind::Path(usvg::Path {
@@ -315,44 +375,30 @@ fn render_thompson(tree: &usvg::Tree, thompson: &ThompsonBetaState) {
 				width: usvg::StrokeWidth::new(0.02),
 				..usvg::Stroke::default()
 			}),
-			transform: usvg::Transform::new(52.92, 0.0, 0.0, -26.46, 31.7625, 232.815),
+			transform,
 			..usvg::Path::default()
 		}));
+	};
+	for (coin, (a, b)) in thompson.a.iter().zip(thompson.b.iter()).enumerate() {
+		append(*a, *b, coin_paint(layout, coin));
 	}
-	append(
-		tree,
-		a1 as f64,
-		b1 as f64,
-		usvg::Paint::Color(usvg::Color::new_rgb(0, 157, 255)),
-	);
-	append(
-		tree,
-		a2 as f64,
-		b2 as f64,
-		usvg::Paint::Color(usvg::Color::new_rgb(255, 95, 89)),
-	);
-	append(
-		tree,
-		a3 as f64,
-		b3 as f64,
-		usvg::Paint::Color(usvg::Color::new_rgb(0, 176, 89)),
-	);
-	// block out anything above
+	// block out anything above, up to the top of the frame
+	let top = tree.svg_node().view_box.rect.y();
+	let (left, right) = (panel.x, panel.x + panel.width);
 	tree.root().append_kind(usvg::NodeKind::Path(usvg::Path {
 		data: std::rc::Rc::new(usvg::PathData(vec![
 			PathSegment::MoveTo {
-				x: 0.0,
-				y: 1.1969697,
+				x: left,
+				y: panel.y,
 			},
 			PathSegment::LineTo {
-				x: 1.0,
-				y: 1.1969697,
+				x: right,
+				y: panel.y,
 			},
-			PathSegment::LineTo { x: 1.0, y: 50.0 },
-			PathSegment::LineTo { x: 0.0, y: 50.0 },
+			PathSegment::LineTo { x: right, y: top },
+			PathSegment::LineTo { x: left, y: top },
 			PathSegment::ClosePath,
 		])),
-		transform: usvg::Transform::new(52.92, 0.0, 0.0, -52.92, 31.7625, 232.815),
 		fill: Some(usvg::Fill::from_paint(usvg::Paint::Color(
 			usvg::Color::new_rgb(255, 255, 255),
 		))),
@@ -361,27 +407,20 @@ fn render_thompson(tree: &usvg::Tree, thompson: &ThompsonBetaState) {
 	}));
 }
 
-fn render_ucb(tree: &usvg::Tree, ucb: &UcbCountState) {
-	let temp = vec![ucb.past[0], ucb.past[1], ucb.past[2]].iter().map(|(a, b)| {
-		let a = *a as f64;
-		let b = *b as f64;
-		let total = a + b;
-		let total_flips = ucb.total_flips as f64;
-		(
-			a / (a + b),
-			a / (a + b)+ f64::sqrt( 2.0 * f64::log(total_flips, 10.0) / total),
-		)
-	}).collect::<Vec<(f64, f64)>>();
-	let ((mean1, upper1), (mean2, upper2), (mean3, upper3)) = (temp[0], temp[1], temp[2]);
-	fn append(tree: &usvg::Tree, mean: f64, upper: f64, stroke_color: usvg::Paint, y_offset: f64) {
-		let path = vec![PathSegment::MoveTo {
-			x: mean,
-			y: y_offset,
-		},
-		PathSegment::LineTo {
-			x: upper,
-			y: y_offset,
-		}
+/// The interval of each coin, from 0 at the left of the panel to 1 at its right
+fn render_ucb(tree: &usvg::Tree, panel: &Rectangle, ucb: &UcbCountState, layout: &Layout) {
+	let bottom = panel.y + panel.height;
+	let transform = usvg::Transform::new(panel.width, 0.0, 0.0, -panel.height, panel.x, bottom);
+	let append = |mean: f64, upper: f64, stroke_color: usvg::Paint, y_offset: f64| {
+		let path = vec![
+			PathSegment::MoveTo {
+				x: mean,
+				y: y_offset,
+			},
+			PathSegment::LineTo {
+				x: upper,
+				y: y_offset,
+			},
 		];
 		tree.root().append_kind(usvg::NodeKind::Path(usvg::Path {
 			data: std::rc::Rc::new(usvg::PathData(path)),
@@ -390,61 +429,94 @@ fn render_ucb(tree: &usvg::Tree, ucb: &UcbCountState) {
 				width: usvg::StrokeWidth::new(0.02),
 				..usvg::Stroke::default()
 			}),
-			transform: usvg::Transform::new(26.46, 0.0, 0.0, -52.92, 116.445, 232.815),
+			transform,
 			..usvg::Path::default()
 		}));
+	};
+	// spread the intervals evenly over the height of the graph
+	let spacing = 1.0 / (ucb.past.len() + 1) as f64;
+	for (coin, (mean, upper)) in ucb.intervals().into_iter().enumerate() {
+		append(mean, upper, coin_paint(layout, coin), spacing * (coin + 1) as f64);
 	}
-	append(
-		tree,
-		mean1,
-		upper1,
-		usvg::Paint::Color(usvg::Color::new_rgb(0, 157, 255)),
-		0.25,
-	);
-		append(
-		tree,
-		mean2,
-		upper2,
-		usvg::Paint::Color(usvg::Color::new_rgb(255, 95, 89)),
-		0.5,
-	);
-	append(
-		tree,
-		mean3,
-		upper3,
-		usvg::Paint::Color(usvg::Color::new_rgb(0, 176, 89)),
-		0.75,
-	);
 }
 
-
-
-
-fn render_text(
-	tree: &usvg::Tree,
-	thompson: (usize, usize),
-	naive: (usize, usize),
-	ucb: (usize, usize),
-	player: (usize, usize),
-) {
-	let mut opt = usvg::Options {
-		..usvg::Options::default()
+/// A chart's axes and lines, and its cursor at the step
+fn render_chart(tree: &usvg::Tree, chart: &Chart, step: usize) {
+	let append = |points: &[(f64, f64)], (r, g, b): (u8, u8, u8), width: f64| {
+		let path = points
+			.iter()
+			.enumerate()
+			.map(|(i, &(x, y))| {
+				if i == 0 {
+					PathSegment::MoveTo { x, y }
+				} else {
+					PathSegment::LineTo { x, y }
+				}
+			})
+			.collect::<Vec<PathSegment>>();
+		if path.is_empty() {
+			return;
+		}
+		tree.root().append_kind(usvg::NodeKind::Path(usvg::Path {
+			data: std::rc::Rc::new(usvg::PathData(path)),
+			stroke: Some(usvg::Stroke {
+				paint: usvg::Paint::Color(usvg::Color::new_rgb(r, g, b)),
+				width: usvg::StrokeWidth::new(width),
+				linejoin: usvg::LineJoin::Round,
+				..usvg::Stroke::default()
+			}),
+			..usvg::Path::default()
+		}));
 	};
-	opt.fontdb.load_system_fonts();
-	let svg_data = include_str!("../text_template.svg")
-		.replace(
-			"## THOMPSON COUNT ##",
-			&format!("{}/{}", thompson.0, thompson.0 + thompson.1),
-		)
-		.replace(
-			"## NAIVE COUNT ##",
-			&format!("{}/{}", naive.0, naive.0 + naive.1),
+	append(&chart.axes(), (0, 0, 0), 0.264583);
+	for line in &chart.lines {
+		append(&line.points, line.color, 0.5);
+	}
+	append(&chart.cursor(step), (128, 128, 128), 0.3);
+}
+
+/// Write each label, laid out by usvg with the fonts it found
+fn render_text(tree: &usvg::Tree, opt: &usvg::Options, text: &[Label]) {
+	if text.is_empty() {
+		return;
+	}
+	// the same size as the frame, so the text lands where it would in the template
+	let mut svg_data = {
+		let svg = tree.svg_node();
+		let view_box = svg.view_box.rect;
+		format!(
+			r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+			svg.size.width(),
+			svg.size.height(),
+			view_box.x(),
+			view_box.y(),
+			view_box.width(),
+			view_box.height()
 		)
-		.replace("## UCB COUNT ##", &format!("{}/{}", ucb.0, ucb.0 + ucb.1))
-		.replace(
-			"## PLAYER COUNT ##",
-			&format!("{}/{}", player.0, player.0 + player.1),
+	};
+	for label in text {
+		let anchor = match label.align {
+			Align::Start => "start",
+			Align::Middle => "middle",
+			Align::End => "end",
+		};
+		svg_data += &format!(
+			r#"<text x="{}" y="{}" style="font-size:{}px;line-height:1.25;font-family:sans-serif;text-anchor:{}">{}</text>"#,
+			label.x,
+			label.y,
+			label.font_size,
+			anchor,
+			escape(&label.text)
 		);
-	let text_tree = usvg::Tree::from_data(&svg_data.as_bytes(), &opt.to_ref()).unwrap();
+	}
+	svg_data += "</svg>";
+	let text_tree = usvg::Tree::from_data(svg_data.as_bytes(), &opt.to_ref()).unwrap();
 	tree.root().append(text_tree.root().make_deep_copy());
 }
+
+/// Usernames may hold anything
+fn escape(text: &str) -> String {
+	text.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+}
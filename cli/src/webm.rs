@@ -0,0 +1,206 @@
+//! Encoding frames as AV1 with rav1e and muxing them into a WebM file, which browsers and slides
+//! can play. Only the parts of WebM a single video track needs are written
+use rav1e::prelude::*;
+
+/// Timestamps are in milliseconds
+const TIMECODE_SCALE: u64 = 1_000_000;
+/// Block timestamps are 16 bit offsets from their cluster's, so start a new one well before that
+const CLUSTER_LENGTH: u64 = 30_000;
+
+pub struct Webm {
+	path: String,
+	width: usize,
+	height: usize,
+	fps: u16,
+	context: Context<u8>,
+	/// The frame number, whether it is a key frame, and the encoded frame
+	packets: Vec<(u64, bool, Vec<u8>)>,
+}
+
+impl Webm {
+	pub fn new(path: &str, width: u32, height: u32, fps: u16) -> Webm {
+		let encoder = EncoderConfig {
+			width: width as usize,
+			height: height as usize,
+			time_base: Rational::new(1, fps as u64),
+			// frames come out in the order they went in
+			low_latency: true,
+			speed_settings: SpeedSettings::from_preset(10),
+			..EncoderConfig::default()
+		};
+		let context = Config::new()
+			.with_encoder_config(encoder)
+			.new_context()
+			.expect("Could not start the video encoder");
+		Webm {
+			path: path.to_string(),
+			width: width as usize,
+			height: height as usize,
+			fps,
+			context,
+			packets: Vec::new(),
+		}
+	}
+	pub fn add(&mut self, frame: &tiny_skia::Pixmap) {
+		let (y, u, v) = yuv420(frame.data(), self.width, self.height);
+		let mut input = self.context.new_frame();
+		input.planes[0].copy_from_raw_u8(&y, self.width, 1);
+		input.planes[1].copy_from_raw_u8(&u, self.width.div_ceil(2), 1);
+		input.planes[2].copy_from_raw_u8(&v, self.width.div_ceil(2), 1);
+		self.context
+			.send_frame(input)
+			.expect("Could not encode the frame");
+		self.receive();
+	}
+	/// Take whatever the encoder has finished
+	fn receive(&mut self) {
+		loop {
+			match self.context.receive_packet() {
+				Ok(packet) => self.packets.push((
+					packet.input_frameno,
+					packet.frame_type == FrameType::KEY,
+					packet.data,
+				)),
+				Err(EncoderStatus::Encoded) => continue,
+				Err(EncoderStatus::NeedMoreData) | Err(EncoderStatus::LimitReached) => break,
+				Err(e) => panic!("Could not encode the video: {}", e),
+			}
+		}
+	}
+	/// Encode the frames still in the encoder, then write the file
+	pub fn finish(mut self) {
+		self.context.flush();
+		self.receive();
+		let millis = |frame: u64| frame * 1000 / self.fps as u64;
+
+		let mut segment = element(
+			&[0x15, 0x49, 0xA9, 0x66],
+			&[
+				uint(&[0x2A, 0xD7, 0xB1], TIMECODE_SCALE),
+				string(&[0x4D, 0x80], "coins"),
+				string(&[0x57, 0x41], "coins"),
+				float(&[0x44, 0x89], millis(self.packets.len() as u64) as f64),
+			]
+			.concat(),
+		);
+		let video = element(
+			&[0xE0],
+			&[
+				uint(&[0xB0], self.width as u64),
+				uint(&[0xBA], self.height as u64),
+			]
+			.concat(),
+		);
+		let track = element(
+			&[0xAE],
+			&[
+				uint(&[0xD7], 1),
+				uint(&[0x73, 0xC5], 1),
+				// video
+				uint(&[0x83], 1),
+				string(&[0x86], "V_AV1"),
+				element(&[0x63, 0xA2], &self.context.container_sequence_header()),
+				video,
+			]
+			.concat(),
+		);
+		segment.extend(element(&[0x16, 0x54, 0xAE, 0x6B], &track));
+
+		// a cluster starts at every key frame, and often enough to keep offsets small
+		let mut cluster: Option<(u64, Vec<u8>)> = None;
+		for (frame, key, data) in &self.packets {
+			let time = millis(*frame);
+			if let Some((start, body)) = cluster.take() {
+				if *key || time - start > CLUSTER_LENGTH {
+					segment.extend(element(&[0x1F, 0x43, 0xB6, 0x75], &body));
+				} else {
+					cluster = Some((start, body));
+				}
+			}
+			let (start, body) = cluster.get_or_insert_with(|| (time, uint(&[0xE7], time)));
+			let mut block = vec![0x81];
+			block.extend(((time - *start) as i16).to_be_bytes());
+			block.push(if *key { 0x80 } else { 0x00 });
+			block.extend(data);
+			body.extend(element(&[0xA3], &block));
+		}
+		if let Some((_, body)) = cluster {
+			segment.extend(element(&[0x1F, 0x43, 0xB6, 0x75], &body));
+		}
+
+		let header = element(
+			&[0x1A, 0x45, 0xDF, 0xA3],
+			&[
+				uint(&[0x42, 0x86], 1),
+				uint(&[0x42, 0xF7], 1),
+				uint(&[0x42, 0xF2], 4),
+				uint(&[0x42, 0xF3], 8),
+				string(&[0x42, 0x82], "webm"),
+				uint(&[0x42, 0x87], 4),
+				uint(&[0x42, 0x85], 2),
+			]
+			.concat(),
+		);
+		let file = [header, element(&[0x18, 0x53, 0x80, 0x67], &segment)].concat();
+		std::fs::write(&self.path, file).expect("Could not write the video");
+	}
+}
+
+/// An EBML element. Sizes always take 8 bytes, which every reader accepts
+fn element(id: &[u8], body: &[u8]) -> Vec<u8> {
+	let mut out = id.to_vec();
+	out.push(0x01);
+	out.extend(&(body.len() as u64).to_be_bytes()[1..]);
+	out.extend(body);
+	out
+}
+
+fn uint(id: &[u8], value: u64) -> Vec<u8> {
+	let bytes = value.to_be_bytes();
+	let first = bytes.iter().position(|b| *b != 0).unwrap_or(7);
+	element(id, &bytes[first..])
+}
+
+fn float(id: &[u8], value: f64) -> Vec<u8> {
+	element(id, &value.to_be_bytes())
+}
+
+fn string(id: &[u8], value: &str) -> Vec<u8> {
+	element(id, value.as_bytes())
+}
+
+/// Convert RGBA pixels to BT.601 Y, U and V planes, with the chroma averaged over each 2x2 block
+fn yuv420(rgba: &[u8], width: usize, height: usize) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+	let pixel = |x: usize, y: usize| {
+		let p = &rgba[(y * width + x) * 4..];
+		(p[0] as f32, p[1] as f32, p[2] as f32)
+	};
+	let mut luma = Vec::with_capacity(width * height);
+	for y in 0..height {
+		for x in 0..width {
+			let (r, g, b) = pixel(x, y);
+			luma.push((16.0 + 0.257 * r + 0.504 * g + 0.098 * b).round() as u8);
+		}
+	}
+	let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+	let mut u = Vec::with_capacity(chroma_width * chroma_height);
+	let mut v = Vec::with_capacity(chroma_width * chroma_height);
+	for cy in 0..chroma_height {
+		for cx in 0..chroma_width {
+			let (mut r, mut g, mut b, mut n) = (0.0, 0.0, 0.0, 0.0);
+			for y in (cy * 2)..(cy * 2 + 2).min(height) {
+				for x in (cx * 2)..(cx * 2 + 2).min(width) {
+					let p = pixel(x, y);
+					r += p.0;
+					g += p.1;
+					b += p.2;
+					n += 1.0;
+				}
+			}
+			let (r, g, b) = (r / n, g / n, b / n);
+			u.push((128.0 - 0.148 * r - 0.291 * g + 0.439 * b).round() as u8);
+			v.push((128.0 + 0.439 * r - 0.368 * g - 0.071 * b).round() as u8);
+		}
+	}
+	(luma, u, v)
+}
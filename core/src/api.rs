@@ -0,0 +1,44 @@
+//! Request and response bodies of the server's `/api/v1` endpoints. Errors are sent as an
+//! [`ErrorBody`](crate::ErrorBody).
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /api/v1/login`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoginRequest {
+	pub username: String,
+}
+
+/// Response to `POST /api/v1/login`. The session cookie is set alongside it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Login {
+	/// The player's id, as written to the dump
+	pub id: String,
+	/// The username the player chose
+	pub name: String,
+}
+
+/// Response to `POST /api/v1/flip/{coin}`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Flip {
+	/// Heads or not
+	pub result: bool,
+	/// Heads minus tails over all of the player's flips this round
+	pub score: i32,
+	/// The position of this flip among the player's flips this round, counting from 0
+	pub index: usize,
+	/// How many flips the player has left, if limited
+	pub flips_left: Option<usize>,
+}
+
+/// Response to `GET /api/v1/player`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Player {
+	pub id: String,
+	pub name: String,
+	/// Heads minus tails this round
+	pub score: i32,
+	/// How many flips the player has made this round
+	pub flips: usize,
+	/// How many flips the player has left, if limited
+	pub flips_left: Option<usize>,
+}
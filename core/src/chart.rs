@@ -0,0 +1,178 @@
+//! Time series across the whole session, from a [`History`], where the layout's chart panels put
+//! them. Panels of the same kind over the same place share a chart, so the strategies can be
+//! compared on one
+use crate::{Align, History, Kind, Label, Panel, Rectangle};
+
+/// A chart's lines, scaled into its panel, and what to write around it
+#[derive(Clone)]
+pub struct Chart {
+	pub rect: Rectangle,
+	pub title: String,
+	/// The value at the top of the chart. The bottom is 0
+	pub top: f64,
+	pub lines: Vec<ChartLine>,
+	/// The number of steps across the chart
+	pub length: usize,
+	pub font_size: f64,
+}
+
+/// A strategy's values, or a coin's, at every step it has one
+#[derive(Clone)]
+pub struct ChartLine {
+	pub color: (u8, u8, u8),
+	pub points: Vec<(f64, f64)>,
+}
+
+/// A line's color and its value at every step, if it has one
+type Values = ((u8, u8, u8), Vec<Option<f64>>);
+
+/// The charts of the chart panels, with the player at `player` for the player's lines and coins in
+/// the given colors
+pub fn charts(
+	history: &History,
+	panels: &[Panel],
+	player: usize,
+	color: impl Fn(usize) -> (u8, u8, u8),
+) -> Vec<Chart> {
+	// the values of every line, until the charts' tops are known
+	let mut charts: Vec<(Kind, &Panel, Vec<Values>)> = Vec::new();
+	for panel in panels.iter().filter(|panel| panel.kind.is_chart()) {
+		let series = match history.series(panel.strategy, player) {
+			Some(series) => series,
+			None => continue,
+		};
+		let lines = match panel.kind {
+			Kind::Heads => vec![(
+				panel.line_color(),
+				series.heads.iter().map(|heads| Some(*heads as f64)).collect(),
+			)],
+			Kind::Best => vec![(
+				panel.line_color(),
+				series.best.iter().copied().map(Some).collect(),
+			)],
+			_ => (0..series.estimates.first().map_or(0, Vec::len))
+				.map(|coin| {
+					let estimates = series.estimates.iter().map(|step| step[coin]).collect();
+					(color(coin), estimates)
+				})
+				.collect(),
+		};
+		let shared = charts.iter_mut().find(|(kind, other, _)| {
+			*kind == panel.kind && same_place(&other.rect(), &panel.rect())
+		});
+		match shared {
+			Some((_, _, shared)) => shared.extend(lines),
+			None => charts.push((panel.kind, panel, lines)),
+		}
+	}
+	charts
+		.into_iter()
+		.map(|(kind, panel, lines)| {
+			let top = match kind {
+				Kind::Heads => lines
+					.iter()
+					.flat_map(|(_, values)| values.iter().flatten())
+					.fold(1.0, |top: f64, value| top.max(*value)),
+				_ => 1.0,
+			};
+			let title = match kind {
+				Kind::Heads => "Heads".to_string(),
+				Kind::Best => "Flips on the best coin".to_string(),
+				_ => format!("{} estimates", panel.strategy.title()),
+			};
+			let mut chart = Chart {
+				rect: panel.rect(),
+				title,
+				top,
+				lines: Vec::new(),
+				length: history.length,
+				font_size: panel.font_size,
+			};
+			chart.lines = lines
+				.into_iter()
+				.map(|(color, values)| ChartLine {
+					color,
+					points: values
+						.iter()
+						.enumerate()
+						.filter_map(|(step, value)| Some((chart.x(step), chart.y((*value)?))))
+						.collect(),
+				})
+				.collect();
+			chart
+		})
+		.collect()
+}
+
+fn same_place(a: &Rectangle, b: &Rectangle) -> bool {
+	a.x == b.x && a.y == b.y && a.width == b.width && a.height == b.height
+}
+
+impl Chart {
+	/// Where the step is across the chart
+	pub fn x(&self, step: usize) -> f64 {
+		self.rect.x + self.rect.width * step as f64 / self.length.saturating_sub(1).max(1) as f64
+	}
+	/// Where the value is up the chart
+	pub fn y(&self, value: f64) -> f64 {
+		self.rect.y + self.rect.height * (1.0 - value / self.top)
+	}
+	/// The cursor at the step, from the top of the chart to the bottom
+	pub fn cursor(&self, step: usize) -> [(f64, f64); 2] {
+		let x = self.x(step);
+		[(x, self.rect.y), (x, self.rect.y + self.rect.height)]
+	}
+	/// The left and bottom edges
+	pub fn axes(&self) -> [(f64, f64); 3] {
+		let Rectangle {
+			x,
+			y,
+			width,
+			height,
+		} = self.rect;
+		[(x, y), (x, y + height), (x + width, y + height)]
+	}
+	/// The title above the chart, the values at the top and bottom on its left and the number of
+	/// steps under its right
+	pub fn labels(&self) -> Vec<Label> {
+		let Rectangle {
+			x,
+			y,
+			width,
+			height,
+		} = self.rect;
+		let label = |x: f64, y: f64, align: Align, text: String| Label {
+			x,
+			y,
+			align,
+			font_size: self.font_size,
+			text,
+		};
+		let gap = self.font_size * 0.3;
+		vec![
+			label(x, y - gap, Align::Start, self.title.clone()),
+			label(x - gap, y + self.font_size * 0.7, Align::End, format!("{}", self.top)),
+			label(x - gap, y + height, Align::End, "0".to_string()),
+			label(
+				x + width,
+				y + height + self.font_size + gap,
+				Align::End,
+				format!("{} steps", self.length),
+			),
+		]
+	}
+	/// The bottom right corner of everything drawn for the chart, with a margin
+	pub fn extent(&self) -> (f64, f64) {
+		let bottom = self.rect.y + self.rect.height + self.font_size * 2.5;
+		(self.rect.x + self.rect.width + self.font_size, bottom)
+	}
+}
+
+/// The points joined up, as an SVG path
+pub fn svg_path(points: &[(f64, f64)]) -> String {
+	points
+		.iter()
+		.enumerate()
+		.map(|(i, (x, y))| format!("{} {} {} ", if i == 0 { "M" } else { "L" }, x, y))
+		.collect()
+}
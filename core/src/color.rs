@@ -0,0 +1,40 @@
+/// The fill for each coin, so that every page and image agrees on which coin is which.
+/// The first three are the original palette, the rest are spread around the color wheel.
+pub fn coin_color(index: usize) -> (u8, u8, u8) {
+	match index {
+		0 => (0, 157, 255),
+		1 => (255, 95, 89),
+		2 => (0, 176, 89),
+		_ => {
+			// golden angle keeps neighbouring coins far apart in hue
+			let hue = (index as f64 * 137.507_764) % 360.0;
+			hsl_to_rgb(hue, 0.75, 0.5)
+		}
+	}
+}
+
+/// The color of a coin as a css `rgb(r,g,b)` string
+pub fn coin_css_color(index: usize) -> String {
+	css_color(coin_color(index))
+}
+
+/// A color as a css `rgb(r,g,b)` string
+pub fn css_color((r, g, b): (u8, u8, u8)) -> String {
+	format!("rgb({},{},{})", r, g, b)
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> (u8, u8, u8) {
+	let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+	let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+	let m = lightness - chroma / 2.0;
+	let (r, g, b) = match (hue / 60.0) as u32 {
+		0 => (chroma, x, 0.0),
+		1 => (x, chroma, 0.0),
+		2 => (0.0, chroma, x),
+		3 => (0.0, x, chroma),
+		4 => (x, 0.0, chroma),
+		_ => (chroma, 0.0, x),
+	};
+	let to_byte = |v: f64| ((v + m) * 255.0).round() as u8;
+	(to_byte(r), to_byte(g), to_byte(b))
+}
@@ -0,0 +1,255 @@
+//! A rendered dump small enough to send a whole session to the view: the flips themselves, which
+//! the view replays and renders a step at a time, instead of the shapes for every step
+use crate::render::render;
+use crate::{
+	charts, css_color, Chart, FilteredData, History, Layout, RenderState, RenderedState,
+	RenderedStateContainer, Steps,
+};
+use serde::{Deserialize, Serialize};
+
+/// Written by the text CLI with `--compact`, and by the server when asked
+#[derive(Serialize, Deserialize)]
+pub struct CompactRendered {
+	/// The flips up to the last step, and no further
+	pub data: FilteredData,
+	pub steps: Steps,
+	/// How many points are sampled along each beta distribution
+	pub resolution: usize,
+	pub layout: Layout,
+}
+
+impl CompactRendered {
+	pub fn new(mut data: FilteredData, steps: Steps, resolution: usize, layout: &Layout) -> Self {
+		if let Some(last) = steps.select(data.length()).last() {
+			data.truncate(last + 1);
+		}
+		CompactRendered {
+			data,
+			steps,
+			resolution,
+			layout: layout.clone(),
+		}
+	}
+}
+
+/// A rendered dump in either format, for the view to step through
+pub enum Rendered {
+	/// The shapes for every step, as the text CLI writes by default
+	Full(Box<RenderedStateContainer>),
+	Compact(Box<Replay>),
+}
+
+/// A compact rendered dump, replayed as far as the step last asked for
+pub struct Replay {
+	rendered: CompactRendered,
+	/// The step each index shows
+	steps: Vec<usize>,
+	state: RenderState,
+	/// The next flip to replay
+	next: usize,
+	history: History,
+}
+
+impl Default for Rendered {
+	fn default() -> Self {
+		Rendered::Full(Box::default())
+	}
+}
+
+impl Rendered {
+	/// Read either format. Both are CBOR maps, told apart by their fields
+	pub fn from_slice(bytes: &[u8]) -> Result<Self, String> {
+		if let Ok(container) = serde_cbor::from_slice(bytes) {
+			return Ok(Rendered::Full(Box::new(container)));
+		}
+		let rendered: CompactRendered = serde_cbor::from_slice(bytes)
+			.map_err(|e| format!("Neither a rendered dump nor a compact one, {}", e))?;
+		if rendered.resolution < 2 {
+			return Err(format!("Invalid resolution {}", rendered.resolution));
+		}
+		Ok(Rendered::Compact(Box::new(Replay {
+			steps: rendered.steps.select(rendered.data.length()),
+			state: RenderState::new(&rendered.data),
+			next: 0,
+			history: History::new(&rendered.data),
+			rendered,
+		})))
+	}
+	/// The number of steps
+	pub fn len(&self) -> usize {
+		match self {
+			Rendered::Full(container) => container.state.len(),
+			Rendered::Compact(replay) => replay.steps.len(),
+		}
+	}
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+	pub fn coins(&self) -> usize {
+		match self {
+			// older rendered dumps do not record the number of coins
+			Rendered::Full(container) if container.coins == 0 => container
+				.state
+				.first()
+				.map_or(0, |state| state.thompson_paths.len()),
+			Rendered::Full(container) => container.coins,
+			Rendered::Compact(replay) => replay.rendered.data.coins,
+		}
+	}
+	/// The time series charts, with the player at `player` for the player's lines
+	pub fn charts(&self, player: usize) -> Vec<Chart> {
+		match self {
+			Rendered::Full(container) => container.charts(player),
+			Rendered::Compact(replay) => {
+				let layout = &replay.rendered.layout;
+				charts(&replay.history, &layout.panels, player, |coin| layout.color(coin))
+			}
+		}
+	}
+	/// The color of the given coin as a css `rgb(r,g,b)` string
+	pub fn css_color(&self, coin: usize) -> String {
+		match self {
+			Rendered::Full(container) => container.css_color(coin),
+			Rendered::Compact(replay) => css_color(replay.rendered.layout.color(coin)),
+		}
+	}
+	/// The id of each player, in the same order as each state's `players`
+	pub fn player_names(&self) -> Vec<&str> {
+		match self {
+			Rendered::Full(container) => container.player_names.iter().map(String::as_str).collect(),
+			Rendered::Compact(replay) => replay
+				.rendered
+				.data
+				.players
+				.iter()
+				.map(|(name, _)| name.as_str())
+				.collect(),
+		}
+	}
+	/// The id of the first of the chosen players
+	pub fn best_player_name(&self) -> &str {
+		match self {
+			Rendered::Full(container) => &container.best_player_name,
+			Rendered::Compact(replay) => replay
+				.rendered
+				.data
+				.players
+				.first()
+				.map_or("", |(name, _)| name.as_str()),
+		}
+	}
+	/// The shapes for the given step. Stepping back through a compact dump replays it from the
+	/// start, which takes far less time than drawing the result
+	pub fn state(&mut self, index: usize) -> Box<RenderedState> {
+		match self {
+			Rendered::Full(container) => container.state[index].clone(),
+			Rendered::Compact(replay) => replay.state(index),
+		}
+	}
+}
+
+impl Replay {
+	fn state(&mut self, index: usize) -> Box<RenderedState> {
+		let data = &self.rendered.data;
+		let step = self.steps[index];
+		if step + 1 < self.next {
+			self.state = RenderState::new(data);
+			self.next = 0;
+		}
+		while self.next <= step {
+			self.state.update(data, self.next);
+			self.next += 1;
+		}
+		let mut output = Box::<RenderedState>::default();
+		render(
+			&self.state,
+			&mut output,
+			self.rendered.resolution,
+			&self.rendered.layout,
+		);
+		output.step = step;
+		output
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Dump;
+
+	/// 10 flips by each algorithm and by a player, heads on every other flip
+	fn data() -> FilteredData {
+		let flips: Vec<(usize, bool)> = (0..10).map(|i| (i % 2, i % 2 == 0)).collect();
+		let dump = Dump {
+			coins: 2,
+			round: 1,
+			coin_probs: vec![0.6, 0.4],
+			algorithms: ["Thompson Strategy", "UCB Strategy", "Naive Strategy"]
+				.iter()
+				.map(|name| (name.to_string(), flips.clone()))
+				.collect(),
+			players: vec![("uuid_jane".to_string(), flips)],
+			flags: Vec::new(),
+			config: String::new(),
+		};
+		let players: Vec<_> = dump.players.iter().collect();
+		dump.filter(&players).unwrap()
+	}
+
+	fn bytes(rendered: &CompactRendered) -> Vec<u8> {
+		serde_cbor::to_vec(rendered).unwrap()
+	}
+
+	#[test]
+	fn keeps_the_flips_up_to_the_last_step() {
+		let steps = Steps {
+			start: 2,
+			end: Some(7),
+			stride: 2,
+		};
+		let rendered = CompactRendered::new(data(), steps, 10, &Layout::default());
+		assert_eq!(rendered.data.length(), 7);
+		assert_eq!(rendered.data.players[0].1.len(), 7);
+	}
+
+	#[test]
+	fn reads_back_what_it_wrote() {
+		let steps = Steps {
+			start: 1,
+			end: None,
+			stride: 3,
+		};
+		let rendered = CompactRendered::new(data(), steps, 10, &Layout::default());
+		let mut read = Rendered::from_slice(&bytes(&rendered)).unwrap();
+		assert!(matches!(read, Rendered::Compact(_)));
+		assert_eq!(read.len(), 4);
+		assert_eq!(read.coins(), 2);
+		assert_eq!(read.player_names(), vec!["uuid_jane"]);
+		assert_eq!(read.best_player_name(), "uuid_jane");
+		let shown: Vec<usize> = (0..read.len()).map(|i| read.state(i).step).collect();
+		assert_eq!(shown, vec![1, 4, 7, 9]);
+	}
+
+	#[test]
+	fn replays_the_same_state_stepping_back() {
+		let rendered = CompactRendered::new(data(), Steps::default(), 10, &Layout::default());
+		let mut read = Rendered::from_slice(&bytes(&rendered)).unwrap();
+		let forward = read.state(3);
+		read.state(9);
+		let back = read.state(3);
+		assert_eq!(back.step, 3);
+		assert_eq!(back.thompson_counts, forward.thompson_counts);
+		assert_eq!(back.player_counts, forward.player_counts);
+		assert_eq!(back.thompson_paths, forward.thompson_paths);
+	}
+
+	#[test]
+	fn turns_down_what_it_cannot_replay() {
+		let rendered = CompactRendered::new(data(), Steps::default(), 1, &Layout::default());
+		assert_eq!(
+			Rendered::from_slice(&bytes(&rendered)).err(),
+			Some("Invalid resolution 1".to_string())
+		);
+		assert!(Rendered::from_slice(b"not cbor").is_err());
+	}
+}
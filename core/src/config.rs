@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// The rules of the game, served by the server at `/config` and read by the game page on load
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameConfig {
+	/// The name of the room the players are in
+	pub room: String,
+	/// One entry per coin, in the order the server numbers them
+	pub coins: Vec<CoinConfig>,
+	/// How many flips each player gets, if limited
+	pub flip_budget: Option<usize>,
+	/// The current round, counting from 1
+	pub round: usize,
+	/// When the current round ends, in milliseconds since the unix epoch, if rounds are timed
+	pub round_end: Option<u64>,
+}
+
+/// How a coin is shown on the game page
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CoinConfig {
+	pub label: String,
+	/// A css color
+	pub color: String,
+}
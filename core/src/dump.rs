@@ -0,0 +1,247 @@
+use crate::ranking::{Player, Selection};
+use serde::{Deserialize, Serialize};
+
+/// How many flips the naive strategy explores for, unless the server is told otherwise
+pub const DEFAULT_EXPLORATION: usize = 30;
+/// How wide UCB's confidence bound is, unless the server is told otherwise
+pub const DEFAULT_CONFIDENCE: f64 = 2.0;
+/// The heads and tails Thompson's beta distributions start from, unless the server is told
+/// otherwise
+pub const DEFAULT_PRIOR: [f64; 2] = [1.0, 1.0];
+
+/// The state of the server at the time of a flush. Written to `dump.cbor`.
+#[derive(Serialize, Deserialize)]
+pub struct Dump {
+	/// The number of coins. Older dumps do not record it, in which case it is inferred from the flips
+	#[serde(default)]
+	pub coins: usize,
+	/// The round the dump was taken in, counting from 1. Older dumps do not record it
+	#[serde(default)]
+	pub round: usize,
+	/// The probability of heads for each coin during that round. Older dumps do not record it
+	#[serde(default)]
+	pub coin_probs: Vec<f64>,
+	/// The name of each algorithm and which coin it flipped with the result
+	pub algorithms: Vec<(String, Vec<(usize, bool)>)>,
+	/// The id of each player and which coin they flipped with the result
+	pub players: Vec<(String, Vec<(usize, bool)>)>,
+	/// Players whose flips looked automated. Older dumps do not record it
+	#[serde(default)]
+	pub flags: Vec<Flag>,
+	/// The server's config as TOML, with the secret and the admin password hidden. Older dumps do
+	/// not record it
+	#[serde(default)]
+	pub config: String,
+}
+
+/// A player whose flips looked automated
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Flag {
+	/// The player's id
+	pub player: String,
+	/// Why they were flagged
+	pub reason: String,
+	/// How many flips they had made when they were flagged
+	pub flips: usize,
+}
+
+impl Dump {
+	pub fn load(path: &str) -> Self {
+		Dump::read(path).unwrap_or_else(|e| panic!("{}", e))
+	}
+	/// Read a dump written as CBOR or as JSON, whatever the file is called
+	pub fn read(path: &str) -> Result<Self, String> {
+		let bytes =
+			std::fs::read(path).map_err(|e| format!("Could not read the dump {}: {}", path, e))?;
+		Dump::from_slice(&bytes).map_err(|e| format!("Could not read the dump {}: {}", path, e))
+	}
+	/// JSON starts with `{`, possibly after whitespace, which never starts a CBOR map
+	pub fn from_slice(bytes: &[u8]) -> Result<Self, String> {
+		match bytes.iter().find(|b| !b.is_ascii_whitespace()) {
+			Some(b'{') => serde_json::from_slice(bytes).map_err(|e| format!("invalid JSON, {}", e)),
+			_ => serde_cbor::from_slice(bytes).map_err(|e| format!("invalid CBOR, {}", e)),
+		}
+	}
+	/// The flips of the best player, ranked by proportion of heads among players with enough
+	/// flips
+	pub fn to_filtered(&self) -> Result<FilteredData, String> {
+		self.filter(&self.select(&Selection::default()).unwrap_or_default())
+	}
+	/// The flips of the algorithms and of the given players, with the algorithms' parameters from
+	/// the config. Fails if the config cannot be read, rather than replaying with other parameters
+	pub fn filter(&self, players: &[&Player]) -> Result<FilteredData, String> {
+		let mut filtered = FilteredData::new();
+		for (algorithm, data) in &self.algorithms {
+			match algorithm.as_str() {
+				"Thompson Strategy" => {
+					filtered.thompson = data.clone();
+				}
+				"UCB Strategy" => {
+					filtered.ucb = data.clone();
+				}
+				"Naive Strategy" => {
+					filtered.naive = data.clone();
+				}
+				_ => (),
+			}
+		}
+		filtered.players = players.iter().map(|player| (*player).clone()).collect();
+		filtered.coins = self.coins.max(self.inferred_coins());
+		let strategies = self.strategies()?;
+		filtered.exploration = strategies
+			.naive
+			.map_or(DEFAULT_EXPLORATION, |naive| naive.exploration);
+		filtered.confidence = strategies.ucb.map_or(DEFAULT_CONFIDENCE, |ucb| ucb.confidence);
+		filtered.prior = strategies
+			.thompson
+			.map_or(DEFAULT_PRIOR, |thompson| thompson.prior);
+		filtered.best = best_coin(&self.probabilities());
+		Ok(filtered)
+	}
+	/// The parameters of the algorithms, from the config. Older dumps do not record them, in which
+	/// case they are the defaults
+	fn strategies(&self) -> Result<Strategies, String> {
+		#[derive(Deserialize, Default)]
+		#[serde(default)]
+		struct Config {
+			strategies: Strategies,
+		}
+		toml::from_str::<Config>(&self.config)
+			.map(|config| config.strategies)
+			.map_err(|e| format!("Could not read the dump's config: {}", e))
+	}
+	/// One more than the highest coin index flipped by anyone
+	fn inferred_coins(&self) -> usize {
+		self.algorithms
+			.iter()
+			.chain(self.players.iter())
+			.flat_map(|(_, past)| past.iter())
+			.map(|(coin, _)| coin + 1)
+			.max()
+			.unwrap_or(0)
+	}
+}
+
+/// The parts of the server's `[strategies]` table the replay needs
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Strategies {
+	naive: Option<Naive>,
+	ucb: Option<Ucb>,
+	thompson: Option<Thompson>,
+}
+
+#[derive(Deserialize)]
+struct Naive {
+	exploration: usize,
+}
+
+#[derive(Deserialize)]
+struct Ucb {
+	confidence: f64,
+}
+
+#[derive(Deserialize)]
+struct Thompson {
+	prior: [f64; 2],
+}
+
+/// The flips of each algorithm and of the chosen players
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FilteredData {
+	pub thompson: Vec<(usize, bool)>,
+	pub naive: Vec<(usize, bool)>,
+	pub ucb: Vec<(usize, bool)>,
+	/// The id and flips of each chosen player, in the order they were chosen
+	pub players: Vec<Player>,
+	/// The number of coins
+	pub coins: usize,
+	/// How many flips the naive strategy explored for
+	pub exploration: usize,
+	/// How wide UCB's confidence bound was. Older compact rendered dumps do not record it
+	#[serde(default = "default_confidence")]
+	pub confidence: f64,
+	/// The heads and tails Thompson's beta distributions started from. Older compact rendered
+	/// dumps do not record them
+	#[serde(default = "default_prior")]
+	pub prior: [f64; 2],
+	/// The coin most likely to come up heads, from the dump. Older compact rendered dumps do not
+	/// record it
+	#[serde(default)]
+	pub best: Option<usize>,
+}
+
+impl FilteredData {
+	/// The longest number of turns taken. Thompson, UCB and naive should all have the same length
+	pub fn length(&self) -> usize {
+		self.players
+			.iter()
+			.map(|(_, past)| past.len())
+			.fold(self.thompson.len(), usize::max)
+	}
+	/// Drop the flips after the first `length`
+	pub fn truncate(&mut self, length: usize) {
+		self.thompson.truncate(length);
+		self.naive.truncate(length);
+		self.ucb.truncate(length);
+		for (_, past) in &mut self.players {
+			past.truncate(length);
+		}
+	}
+	/// The coin most likely to come up heads, as recorded or else the one that came up heads
+	/// most often in these flips
+	pub fn best_coin(&self) -> usize {
+		self.best.unwrap_or_else(|| {
+			let mut counts = vec![(0, 0); self.coins];
+			for (coin, heads) in [&self.thompson, &self.naive, &self.ucb]
+				.iter()
+				.copied()
+				.chain(self.players.iter().map(|(_, past)| past))
+				.flat_map(|past| past.iter())
+			{
+				if let Some((flips, all_heads)) = counts.get_mut(*coin) {
+					*flips += 1;
+					*all_heads += *heads as usize;
+				}
+			}
+			let proportions: Vec<f64> = counts
+				.iter()
+				.map(|(flips, heads)| *heads as f64 / (*flips as f64).max(1.0))
+				.collect();
+			best_coin(&proportions).unwrap_or(0)
+		})
+	}
+	fn new() -> Self {
+		Self {
+			thompson: Vec::new(),
+			naive: Vec::new(),
+			ucb: Vec::new(),
+			players: Vec::new(),
+			coins: 0,
+			exploration: DEFAULT_EXPLORATION,
+			confidence: DEFAULT_CONFIDENCE,
+			prior: DEFAULT_PRIOR,
+			best: None,
+		}
+	}
+}
+
+fn default_confidence() -> f64 {
+	DEFAULT_CONFIDENCE
+}
+
+fn default_prior() -> [f64; 2] {
+	DEFAULT_PRIOR
+}
+
+/// The coin with the highest probability, the first of any ties
+fn best_coin(probs: &[f64]) -> Option<usize> {
+	probs
+		.iter()
+		.enumerate()
+		.fold(None, |best: Option<(usize, f64)>, (coin, &prob)| match best {
+			Some((_, best_prob)) if best_prob >= prob => best,
+			_ => Some((coin, prob)),
+		})
+		.map(|(coin, _)| coin)
+}
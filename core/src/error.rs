@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// The body of an error response from the server
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ErrorBody {
+	/// A short, stable name for the error that pages can match on
+	pub code: String,
+	/// A message to show the player
+	pub message: String,
+}
@@ -0,0 +1,88 @@
+//! How each strategy and each chosen player did over the whole session, a value for every step,
+//! for the time series charts
+use crate::{FilteredData, Strategy};
+use serde::{Deserialize, Serialize};
+
+/// A series for each strategy and each chosen player, all as long as the session
+#[derive(Clone, Serialize, Deserialize)]
+pub struct History {
+	pub thompson: Series,
+	pub naive: Series,
+	pub ucb: Series,
+	/// One for each chosen player, in order
+	pub players: Vec<Series>,
+	/// The coin most likely to come up heads
+	pub best: usize,
+	/// The number of steps
+	pub length: usize,
+}
+
+/// What one strategy or player had done after each step. Players who stopped early keep their
+/// last values
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Series {
+	/// How many heads so far
+	pub heads: Vec<usize>,
+	/// The proportion of flips so far on the best coin
+	pub best: Vec<f64>,
+	/// The proportion of heads so far on each coin, once it has been flipped
+	pub estimates: Vec<Vec<Option<f64>>>,
+}
+
+impl History {
+	pub fn new(data: &FilteredData) -> Self {
+		let length = data.length();
+		let best = data.best_coin();
+		let series = |past: &[(usize, bool)]| Series::new(past, data.coins, best, length);
+		History {
+			thompson: series(&data.thompson),
+			naive: series(&data.naive),
+			ucb: series(&data.ucb),
+			players: data.players.iter().map(|(_, past)| series(past)).collect(),
+			best,
+			length,
+		}
+	}
+	/// The series of the strategy, with `player` as the chosen player
+	pub fn series(&self, strategy: Strategy, player: usize) -> Option<&Series> {
+		match strategy {
+			Strategy::Thompson => Some(&self.thompson),
+			Strategy::Naive => Some(&self.naive),
+			Strategy::Ucb => Some(&self.ucb),
+			Strategy::Player => self.players.get(player),
+		}
+	}
+}
+
+impl Series {
+	fn new(past: &[(usize, bool)], coins: usize, best: usize, length: usize) -> Self {
+		let mut series = Series::default();
+		let mut heads = 0;
+		let mut on_best = 0;
+		// the heads and flips on each coin
+		let mut counts = vec![(0, 0); coins];
+		for step in 0..length {
+			if let Some((coin, result)) = past.get(step) {
+				heads += *result as usize;
+				on_best += (*coin == best) as usize;
+				if let Some((coin_heads, flips)) = counts.get_mut(*coin) {
+					*coin_heads += *result as usize;
+					*flips += 1;
+				}
+			}
+			let flips = (step + 1).min(past.len());
+			series.heads.push(heads);
+			series.best.push(on_best as f64 / flips.max(1) as f64);
+			series.estimates.push(
+				counts
+					.iter()
+					.map(|(heads, flips)| match flips {
+						0 => None,
+						flips => Some(*heads as f64 / *flips as f64),
+					})
+					.collect(),
+			);
+		}
+		series
+	}
+}
@@ -0,0 +1,292 @@
+//! Where each strategy is drawn, read from a TOML file so that the images can be restyled or given
+//! more panels without recompiling. The default is `core/layout.toml`, the layout of the bundled
+//! templates
+use crate::{coin_color, Rectangle};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// The panels drawn on every frame, and the colors of the coins. Written into compact rendered
+/// dumps, for the view to render them the same way
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Layout {
+	/// The color of each coin, in order. Coins past the end keep the usual palette
+	#[serde(default)]
+	colors: Vec<Rgb>,
+	/// Drawn in order, later ones over earlier ones
+	#[serde(rename = "panel", default)]
+	pub panels: Vec<Panel>,
+}
+
+/// A color, written as `#rrggbb`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+struct Rgb(u8, u8, u8);
+
+impl TryFrom<String> for Rgb {
+	type Error = String;
+	fn try_from(color: String) -> Result<Self, Self::Error> {
+		let invalid = || format!("Invalid color {}, expected #rrggbb", color);
+		let hex = color
+			.strip_prefix('#')
+			.filter(|hex| hex.len() == 6 && hex.is_ascii())
+			.ok_or_else(invalid)?;
+		let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+		Ok(Rgb(channel(0)?, channel(2)?, channel(4)?))
+	}
+}
+
+impl From<Rgb> for String {
+	fn from(Rgb(r, g, b): Rgb) -> Self {
+		format!("#{:02x}{:02x}{:02x}", r, g, b)
+	}
+}
+
+/// Whose flips a panel shows
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+	Thompson,
+	Naive,
+	Ucb,
+	/// The chosen player
+	Player,
+}
+
+/// What a panel shows
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+	/// A stacked box per coin, as tall as the share of flips on it
+	Boxes,
+	/// Heads out of flips, as text
+	Count,
+	/// The beta distribution of each coin, for Thompson sampling
+	Curves,
+	/// The confidence interval of each coin, for UCB
+	Intervals,
+	/// The proportion of heads seen on each coin while exploring, for naive
+	Means,
+	/// What naive is doing, as text
+	Status,
+	/// The player's username, as text
+	Name,
+	/// Heads so far at every step of the session, with a cursor at the current step
+	Heads,
+	/// The proportion of flips on the best coin so far at every step
+	Best,
+	/// The proportion of heads seen on each coin so far at every step
+	Estimates,
+}
+
+/// How text lines up with its position
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+	Start,
+	#[default]
+	Middle,
+	End,
+}
+
+/// One thing drawn on every frame
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Panel {
+	pub strategy: Strategy,
+	pub kind: Kind,
+	/// The id of an element in the template whose bounds the panel fills, instead of a position
+	#[serde(default)]
+	pub anchor: Option<String>,
+	#[serde(default)]
+	pub x: f64,
+	#[serde(default)]
+	pub y: f64,
+	#[serde(default)]
+	pub width: f64,
+	#[serde(default)]
+	pub height: f64,
+	/// The density at the top of a curves panel
+	#[serde(default = "default_peak")]
+	pub peak: f64,
+	/// How text lines up with its position
+	#[serde(default)]
+	pub align: Align,
+	/// The font size of text, and of a chart's title and axes
+	#[serde(default = "default_font_size")]
+	pub font_size: f64,
+	/// The color of the strategy's line in heads and best charts. Each strategy has its own if not
+	/// given
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	color: Option<Rgb>,
+}
+
+/// Text to draw, on its baseline
+#[derive(Clone, Debug)]
+pub struct Label {
+	pub x: f64,
+	pub y: f64,
+	pub align: Align,
+	pub font_size: f64,
+	pub text: String,
+}
+
+impl Strategy {
+	/// How the strategy is titled on the page
+	pub fn title(self) -> &'static str {
+		match self {
+			Strategy::Thompson => "Thompson",
+			Strategy::Naive => "Naive",
+			Strategy::Ucb => "UCB",
+			Strategy::Player => "Player",
+		}
+	}
+	/// The color of the line under the strategy's title in the bundled template, or grey for the
+	/// player, whose line is black like naive's
+	fn color(self) -> (u8, u8, u8) {
+		match self {
+			Strategy::Thompson => (29, 0, 255),
+			Strategy::Naive => (0, 0, 0),
+			Strategy::Ucb => (230, 156, 255),
+			Strategy::Player => (140, 140, 140),
+		}
+	}
+}
+
+impl Kind {
+	/// Whether the panel is a time series across the session
+	pub fn is_chart(self) -> bool {
+		matches!(self, Kind::Heads | Kind::Best | Kind::Estimates)
+	}
+}
+
+fn default_peak() -> f64 {
+	2.4
+}
+
+fn default_font_size() -> f64 {
+	4.5861
+}
+
+impl Panel {
+	/// Where the panel is drawn
+	pub fn rect(&self) -> Rectangle {
+		Rectangle {
+			x: self.x,
+			y: self.y,
+			width: self.width,
+			height: self.height,
+		}
+	}
+	/// Where text starts, is centered or ends, on its baseline. Anchored text sits at the bottom
+	/// of the anchor
+	pub fn text_position(&self) -> (f64, f64) {
+		let across = match self.align {
+			Align::Start => 0.0,
+			Align::Middle => 0.5,
+			Align::End => 1.0,
+		};
+		(self.x + self.width * across, self.y + self.height)
+	}
+	/// The text, where the panel places it
+	pub fn label(&self, text: String) -> Label {
+		let (x, y) = self.text_position();
+		Label {
+			x,
+			y,
+			align: self.align,
+			font_size: self.font_size,
+			text,
+		}
+	}
+	/// The color of the panel's line in a chart
+	pub fn line_color(&self) -> (u8, u8, u8) {
+		self.color
+			.map_or_else(|| self.strategy.color(), |Rgb(r, g, b)| (r, g, b))
+	}
+	fn is_text(&self) -> bool {
+		matches!(self.kind, Kind::Count | Kind::Status | Kind::Name)
+	}
+}
+
+impl Default for Layout {
+	fn default() -> Self {
+		include_str!("../layout.toml")
+			.parse()
+			.expect("The default layout is invalid")
+	}
+}
+
+impl FromStr for Layout {
+	type Err = String;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let layout: Layout = toml::from_str(s).map_err(|e| e.to_string())?;
+		for panel in &layout.panels {
+			let fits = match panel.kind {
+				Kind::Boxes | Kind::Count | Kind::Heads | Kind::Best | Kind::Estimates => true,
+				Kind::Curves => panel.strategy == Strategy::Thompson,
+				Kind::Intervals => panel.strategy == Strategy::Ucb,
+				Kind::Means | Kind::Status => panel.strategy == Strategy::Naive,
+				Kind::Name => panel.strategy == Strategy::Player,
+			};
+			if !fits {
+				return Err(format!(
+					"{:?} cannot be drawn for {:?}",
+					panel.kind, panel.strategy
+				));
+			}
+			let sized = panel.width > 0.0 && panel.height > 0.0;
+			if panel.anchor.is_none() && !panel.is_text() && !sized {
+				return Err(format!(
+					"The {:?} panel for {:?} needs a width and a height, or an anchor",
+					panel.kind, panel.strategy
+				));
+			}
+		}
+		Ok(layout)
+	}
+}
+
+impl Layout {
+	/// Read a layout from a TOML file
+	pub fn read(path: &str) -> Result<Self, String> {
+		std::fs::read_to_string(path)
+			.map_err(|e| e.to_string())
+			.and_then(|s| s.parse())
+			.map_err(|e| format!("Could not read the layout {}: {}", path, e))
+	}
+	/// Whether any panel is placed by an element of the template
+	pub fn has_anchors(&self) -> bool {
+		self.panels.iter().any(|panel| panel.anchor.is_some())
+	}
+	/// Place the anchored panels over the bounds of their elements, as found by `bounds`
+	pub fn anchor(&mut self, bounds: impl Fn(&str) -> Option<Rectangle>) -> Result<(), String> {
+		for panel in &mut self.panels {
+			if let Some(anchor) = &panel.anchor {
+				let rect = bounds(anchor).ok_or_else(|| {
+					format!("There is no element with the id {} in the template", anchor)
+				})?;
+				panel.x = rect.x;
+				panel.y = rect.y;
+				panel.width = rect.width;
+				panel.height = rect.height;
+			}
+		}
+		Ok(())
+	}
+	/// The first panel of the kind for the strategy, if it is drawn
+	pub fn panel(&self, strategy: Strategy, kind: Kind) -> Option<&Panel> {
+		self.panels
+			.iter()
+			.find(|panel| panel.strategy == strategy && panel.kind == kind)
+	}
+	/// The panels of time series charts
+	pub fn charts(&self) -> impl Iterator<Item = &Panel> {
+		self.panels.iter().filter(|panel| panel.kind.is_chart())
+	}
+	/// The color of the given coin
+	pub fn color(&self, coin: usize) -> (u8, u8, u8) {
+		self.colors
+			.get(coin)
+			.map_or_else(|| coin_color(coin), |&Rgb(r, g, b)| (r, g, b))
+	}
+}
@@ -0,0 +1,42 @@
+//! Types shared between the server, the rendering CLIs and the view page.
+//!
+//! The server writes a [`Dump`], the CLIs replay it step by step through a [`RenderState`] for the
+//! players chosen with a [`Selection`], and
+//! the text CLI hands a [`RenderedStateContainer`] to the view (rendered by the [`render`] module,
+//! where the [`Layout`] puts it), or the flips themselves in a [`CompactRendered`] for the view to
+//! replay. Both carry a [`History`] of the whole session for the time series [`chart`]s. The game
+//! page is drawn from the server's [`GameConfig`], and the
+//! [`api`] module holds the bodies of the JSON endpoints the pages talk to.
+
+pub mod api;
+pub mod chart;
+pub mod color;
+pub mod compact;
+pub mod config;
+pub mod dump;
+pub mod error;
+pub mod history;
+pub mod layout;
+pub mod ranking;
+pub mod render;
+pub mod rendered;
+pub mod replay;
+pub mod summary;
+
+pub use chart::{charts, Chart, ChartLine};
+pub use color::{coin_color, coin_css_color, css_color};
+pub use compact::{CompactRendered, Rendered};
+pub use config::{CoinConfig, GameConfig};
+pub use dump::{Dump, FilteredData, Flag};
+pub use error::ErrorBody;
+pub use history::{History, Series};
+pub use layout::{Align, Kind, Label, Layout, Panel, Strategy};
+pub use ranking::{Metric, Player, Ranking, Selection};
+pub use rendered::{
+	naive_bars, naive_status, Rectangle, RenderedPlayer, RenderedState, RenderedStateContainer,
+	NAIVE_PANEL,
+};
+pub use replay::{
+	ucb_bound, GeneralState, NaiveMeanState, RenderState, Steps, ThompsonBetaState, UcbCountState,
+};
+pub use summary::{StrategySummary, Summary};
@@ -0,0 +1,347 @@
+//! Choosing which players to replay against the algorithms
+use crate::Dump;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// A player's id and which coin they flipped with the result
+pub type Player = (String, Vec<(usize, bool)>);
+
+/// What players are ranked by
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+	/// The most heads
+	Score,
+	/// The highest proportion of heads
+	Proportion,
+	/// The least regret per flip: how much more likely heads was on the best coin than on the
+	/// coins they flipped, on average
+	Regret,
+}
+
+impl FromStr for Metric {
+	type Err = String;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"score" => Ok(Metric::Score),
+			"proportion" => Ok(Metric::Proportion),
+			"regret" => Ok(Metric::Regret),
+			_ => Err(format!(
+				"Unknown metric {}, expected score, proportion or regret",
+				s
+			)),
+		}
+	}
+}
+
+/// How players are ranked. Flagged players are never ranked
+#[derive(Clone, Copy, Debug)]
+pub struct Ranking {
+	pub metric: Metric,
+	/// Players with fewer flips rank below everyone with enough, so a lucky 1 out of 1 does not
+	/// beat 90 out of 100
+	pub min_flips: usize,
+}
+
+impl Default for Ranking {
+	fn default() -> Self {
+		Ranking {
+			metric: Metric::Proportion,
+			min_flips: 10,
+		}
+	}
+}
+
+/// Which players to replay
+#[derive(Clone, Debug)]
+pub enum Selection {
+	/// The players with these ids or usernames
+	Named(Vec<String>),
+	/// The players at these ranks, counting from 1
+	Ranked(Ranking, Vec<usize>),
+	/// Every player, by username
+	All,
+}
+
+impl Default for Selection {
+	/// The best player
+	fn default() -> Self {
+		Selection::Ranked(Ranking::default(), vec![1])
+	}
+}
+
+impl Selection {
+	/// The players asked for on a command line: everyone if `all`, else the named players, else
+	/// the players at the ranks, or the best player if no ranks are given either
+	pub fn new(all: bool, names: &[String], ranks: &[usize], ranking: Ranking) -> Self {
+		if all {
+			Selection::All
+		} else if !names.is_empty() {
+			Selection::Named(names.to_vec())
+		} else if ranks.is_empty() {
+			Selection::Ranked(ranking, vec![1])
+		} else {
+			Selection::Ranked(ranking, ranks.to_vec())
+		}
+	}
+}
+
+impl Dump {
+	/// The players who were not flagged, best first. Ties are by username
+	pub fn rank(&self, ranking: &Ranking) -> Vec<&Player> {
+		let probs = self.probabilities();
+		let mut ranked: Vec<(bool, f64, &Player)> = self
+			.players
+			.iter()
+			.filter(|(name, _)| !self.flags.iter().any(|flag| &flag.player == name))
+			.map(|player| {
+				let past = &player.1;
+				(
+					past.len() >= ranking.min_flips,
+					value(ranking.metric, past, &probs),
+					player,
+				)
+			})
+			.collect();
+		ranked.sort_by(|a, b| {
+			b.0.cmp(&a.0)
+				.then_with(|| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal))
+				.then_with(|| by_username(a.2, b.2))
+		});
+		ranked.into_iter().map(|(_, _, player)| player).collect()
+	}
+	/// The chosen players, in the order they were asked for
+	pub fn select(&self, selection: &Selection) -> Result<Vec<&Player>, String> {
+		match selection {
+			Selection::All => {
+				let mut players: Vec<&Player> = self.players.iter().collect();
+				players.sort_by(|a, b| by_username(a, b));
+				Ok(players)
+			}
+			Selection::Named(names) => names
+				.iter()
+				.map(|name| {
+					self.find(name)
+						.ok_or_else(|| format!("There is no player {} in the dump", name))
+				})
+				.collect(),
+			Selection::Ranked(ranking, ranks) => {
+				let ranked = self.rank(ranking);
+				// flagged players are never ranked, but can still be picked
+				let flagged = self.players.len() - ranked.len();
+				let missing = |rank: usize| {
+					let mut error =
+						format!("There is no rank {}, {} players are ranked", rank, ranked.len());
+					if flagged > 0 {
+						error += &format!(
+							". Flagged players are not ranked ({} of them), --all or --player picks them",
+							flagged
+						);
+					}
+					error
+				};
+				ranks
+					.iter()
+					.map(|rank| {
+						rank.checked_sub(1)
+							.and_then(|i| ranked.get(i).copied())
+							.ok_or_else(|| missing(*rank))
+					})
+					.collect()
+			}
+		}
+	}
+	/// The player with the given id, or with the given username if no id matches
+	fn find(&self, player: &str) -> Option<&Player> {
+		self.players
+			.iter()
+			.find(|(name, _)| name == player)
+			.or_else(|| {
+				self.players
+					.iter()
+					.find(|(name, _)| name.split_once('_').map(|(_, name)| name) == Some(player))
+			})
+	}
+	/// The probability of heads of each coin, as recorded or else as often as it came up heads
+	pub(crate) fn probabilities(&self) -> Vec<f64> {
+		if !self.coin_probs.is_empty() {
+			return self.coin_probs.clone();
+		}
+		let mut counts = Vec::new();
+		for (coin, heads) in self
+			.algorithms
+			.iter()
+			.chain(self.players.iter())
+			.flat_map(|(_, past)| past.iter())
+		{
+			if counts.len() <= *coin {
+				counts.resize(coin + 1, (0, 0));
+			}
+			counts[*coin].0 += 1;
+			counts[*coin].1 += *heads as usize;
+		}
+		counts
+			.iter()
+			.map(|(flips, heads)| *heads as f64 / (*flips).max(1) as f64)
+			.collect()
+	}
+}
+
+/// Dumps keep the players in no particular order, so they are put in order of username, and of
+/// id for players who share one
+fn by_username(a: &Player, b: &Player) -> Ordering {
+	username(&a.0).cmp(username(&b.0)).then_with(|| a.0.cmp(&b.0))
+}
+
+/// Ids are a uuid and the username
+fn username(id: &str) -> &str {
+	id.split_once('_').map_or(id, |(_, name)| name)
+}
+
+/// How well a player did under the metric. Higher is better
+fn value(metric: Metric, past: &[(usize, bool)], probs: &[f64]) -> f64 {
+	let heads = past.iter().filter(|(_, heads)| *heads).count() as f64;
+	let flips = past.len().max(1) as f64;
+	match metric {
+		Metric::Score => heads,
+		Metric::Proportion => heads / flips,
+		Metric::Regret => {
+			let best = probs.iter().cloned().fold(0.0, f64::max);
+			let regret: f64 = past
+				.iter()
+				.map(|(coin, _)| best - probs.get(*coin).copied().unwrap_or(0.0))
+				.sum();
+			-regret / flips
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::dump::Flag;
+
+	/// `heads` out of `flips` on coin 0, which comes up heads half the time, then coin 1
+	fn flips(heads: usize, flips: usize) -> Vec<(usize, bool)> {
+		(0..flips).map(|i| (i % 2, i < heads)).collect()
+	}
+
+	fn dump() -> Dump {
+		Dump {
+			coins: 2,
+			round: 1,
+			coin_probs: vec![0.5, 0.8],
+			algorithms: Vec::new(),
+			players: vec![
+				("1_lucky".to_string(), flips(1, 1)),
+				("2_bob".to_string(), flips(6, 10)),
+				("3_alice".to_string(), flips(8, 10)),
+				("4_cheat".to_string(), flips(10, 10)),
+				("5_carol".to_string(), flips(6, 10)),
+			],
+			flags: vec![Flag {
+				player: "4_cheat".to_string(),
+				reason: "Too fast".to_string(),
+				flips: 10,
+			}],
+			config: String::new(),
+		}
+	}
+
+	fn ids(players: Vec<&Player>) -> Vec<&str> {
+		players.iter().map(|(id, _)| id.as_str()).collect()
+	}
+
+	#[test]
+	fn ranks_players_with_enough_flips_first_and_ties_by_username() {
+		let dump = dump();
+		assert_eq!(
+			ids(dump.rank(&Ranking::default())),
+			vec!["3_alice", "2_bob", "5_carol", "1_lucky"]
+		);
+		let ranking = Ranking {
+			min_flips: 0,
+			..Ranking::default()
+		};
+		assert_eq!(
+			ids(dump.rank(&ranking)),
+			vec!["1_lucky", "3_alice", "2_bob", "5_carol"]
+		);
+	}
+
+	#[test]
+	fn ranks_by_the_metric() {
+		let dump = dump();
+		let ranking = |metric| Ranking {
+			metric,
+			min_flips: 0,
+		};
+		assert_eq!(
+			ids(dump.rank(&ranking(Metric::Score))),
+			vec!["3_alice", "2_bob", "5_carol", "1_lucky"]
+		);
+		// everyone with 10 flips flipped each coin as often, the lucky player only flipped the
+		// worse coin
+		assert_eq!(
+			ids(dump.rank(&ranking(Metric::Regret))),
+			vec!["3_alice", "2_bob", "5_carol", "1_lucky"]
+		);
+	}
+
+	#[test]
+	fn selects_players_by_id_username_and_rank() {
+		let dump = dump();
+		let named = Selection::Named(vec!["bob".to_string(), "4_cheat".to_string()]);
+		assert_eq!(ids(dump.select(&named).unwrap()), vec!["2_bob", "4_cheat"]);
+		let ranked = Selection::Ranked(Ranking::default(), vec![2, 1]);
+		assert_eq!(ids(dump.select(&ranked).unwrap()), vec!["2_bob", "3_alice"]);
+		assert_eq!(
+			ids(dump.select(&Selection::All).unwrap()),
+			vec!["3_alice", "2_bob", "5_carol", "4_cheat", "1_lucky"]
+		);
+		assert_eq!(
+			ids(dump.select(&Selection::default()).unwrap()),
+			vec!["3_alice"]
+		);
+	}
+
+	#[test]
+	fn fails_to_select_missing_players() {
+		let dump = dump();
+		let named = Selection::Named(vec!["dave".to_string()]);
+		assert_eq!(
+			dump.select(&named).unwrap_err(),
+			"There is no player dave in the dump"
+		);
+		for rank in [0, 5] {
+			let error = dump
+				.select(&Selection::Ranked(Ranking::default(), vec![rank]))
+				.unwrap_err();
+			assert!(
+				error.starts_with(&format!("There is no rank {}, 4 players are ranked", rank)),
+				"{}",
+				error
+			);
+			assert!(error.contains("(1 of them)"), "{}", error);
+		}
+	}
+
+	#[test]
+	fn picks_the_selection_from_the_command_line() {
+		let names = ["bob".to_string()];
+		let ranking = Ranking::default();
+		assert!(matches!(
+			Selection::new(true, &names, &[2], ranking),
+			Selection::All
+		));
+		assert!(matches!(
+			Selection::new(false, &names, &[2], ranking),
+			Selection::Named(names) if names == ["bob"]
+		));
+		assert!(matches!(
+			Selection::new(false, &[], &[], ranking),
+			Selection::Ranked(_, ranks) if ranks == [1]
+		));
+	}
+}
@@ -0,0 +1,233 @@
+//! Turning a dump into the shapes the view page draws. The view does the same for compact rendered
+//! dumps in the browser, so nothing here may use a crate that does not build for wasm
+use crate::{
+	naive_bars, FilteredData, GeneralState, History, Kind, Layout, NaiveMeanState, Rectangle, RenderState,
+	RenderedPlayer, RenderedState, RenderedStateContainer, Steps, Strategy, ThompsonBetaState,
+	UcbCountState,
+};
+
+/// How many points are sampled along each beta distribution, unless told otherwise
+pub const RESOLUTION_OF_DISTRIBUTION_SAMPLING: usize = 80;
+
+/// Replay the algorithms and the chosen players, rendering each of the given steps with `resolution`
+/// points along each beta distribution. The boxes and the naive bars are placed by the layout, the
+/// view page draws everything else where the default layout has it
+pub fn render_dump(
+	data: &FilteredData,
+	steps: &Steps,
+	resolution: usize,
+	layout: &Layout,
+) -> RenderedStateContainer {
+	let mut state = RenderState::new(data);
+	let steps = steps.select(data.length());
+	let names = data.players.iter().map(|(name, _)| name.clone()).collect();
+	let mut output = RenderedStateContainer::new(steps.len(), names, data.coins);
+	output.colors = (0..data.coins).map(|coin| layout.color(coin)).collect();
+	output.history = Some(History::new(data));
+	output.chart_panels = layout.charts().cloned().collect();
+
+	let mut next = 0;
+	for (i, step) in steps.iter().enumerate() {
+		// update what each algorithm sees, up to the step
+		while next <= *step {
+			state.update(data, next);
+			next += 1;
+		}
+		// render to the state
+		render(&state, &mut output.state[i], resolution, layout);
+		output.state[i].step = *step;
+	}
+	output
+}
+
+/// Write the shapes for the given state into the rendered state
+pub fn render(
+	state: &RenderState,
+	base_state: &mut RenderedState,
+	resolution: usize,
+	layout: &Layout,
+) {
+	render_thompson(base_state, &state.thompson.1, resolution);
+	render_ucb(base_state, &state.ucb.1);
+
+	// strategies without a boxes panel get no boxes
+	let rects = |strategy: Strategy, general: &GeneralState| {
+		layout
+			.panel(strategy, Kind::Boxes)
+			.map_or_else(Vec::new, |panel| {
+				boxes(
+					&panel.rect(),
+					&general.count,
+					general.failures + general.successes,
+				)
+			})
+	};
+	base_state.thompson_rects = rects(Strategy::Thompson, &state.thompson.0);
+	base_state.naive_rects = rects(Strategy::Naive, &state.naive.0);
+	render_naive(base_state, &state.naive.1, layout);
+	base_state.ucb_rects = rects(Strategy::Ucb, &state.ucb.0);
+	base_state.players = state
+		.players
+		.iter()
+		.map(|player| RenderedPlayer {
+			rects: rects(Strategy::Player, player),
+			counts: (player.successes, player.failures),
+		})
+		.collect();
+	let first = base_state.players.first().cloned().unwrap_or_default();
+	base_state.player_rects = first.rects;
+
+	render_text(
+		base_state,
+		(state.thompson.0.successes, state.thompson.0.failures),
+		(state.naive.0.successes, state.naive.0.failures),
+		(state.ucb.0.successes, state.ucb.0.failures),
+		first.counts,
+	);
+}
+
+/// Stack one box per coin down the panel, each as tall as the proportion of flips on that coin
+fn boxes(panel: &Rectangle, counts: &[usize], total: usize) -> Vec<Rectangle> {
+	let mut y = panel.y;
+	counts
+		.iter()
+		.map(|count| {
+			let height = panel.height * (*count as f64 / total as f64);
+			let rect = Rectangle {
+				x: panel.x,
+				y,
+				width: panel.width,
+				height,
+			};
+			y += height;
+			rect
+		})
+		.collect()
+}
+
+fn render_naive(state: &mut RenderedState, naive: &NaiveMeanState, layout: &Layout) {
+	state.naive_means = layout
+		.panel(Strategy::Naive, Kind::Means)
+		.map_or_else(Vec::new, |panel| naive_bars(&panel.rect(), &naive.means()));
+	state.naive_explored = (naive.flips.min(naive.exploration), naive.exploration);
+	state.naive_committed = naive.committed;
+}
+
+fn render_thompson(state: &mut RenderedState, thompson: &ThompsonBetaState, resolution: usize) {
+	let append = |a: f64, b: f64| {
+		let mut path = beta_samples(a, b, resolution)
+			.into_iter()
+			.enumerate()
+			.map(|(i, x)| {
+				let command = if i == 0 { "M" } else { "L" };
+				format!("{} {} {} ", command, x, beta_pdf(a, b, x))
+			})
+			.collect::<Vec<String>>();
+		path.push("M 1.0 0.0 Z".to_string());
+		path.iter().fold(String::new(), |acc, x| acc + x)
+	};
+	state.thompson_paths = thompson
+		.a
+		.iter()
+		.zip(thompson.b.iter())
+		.map(|(a, b)| append(*a, *b))
+		.collect();
+}
+
+/// Where to sample the beta distribution with the given shape to draw it with about `resolution`
+/// points, in order. Half are spread evenly across it and half within four standard deviations
+/// of the mode, so that a sharp posterior after many flips is still smooth at its peak
+pub fn beta_samples(a: f64, b: f64, resolution: usize) -> Vec<f64> {
+	const FIRST: f64 = 0.001;
+	const LAST: f64 = 0.999;
+	// `count` points from `low` to `high`, including both ends
+	let spread = |low: f64, high: f64, count: usize| {
+		(0..count).map(move |i| low + (high - low) * i as f64 / (count - 1).max(1) as f64)
+	};
+	let mode = if a + b > 2.0 { (a - 1.0) / (a + b - 2.0) } else { 0.5 };
+	let deviation = (a * b / ((a + b).powi(2) * (a + b + 1.0))).sqrt();
+	let low = (mode - 4.0 * deviation).max(FIRST);
+	let high = (mode + 4.0 * deviation).min(LAST);
+	let even = resolution / 2;
+	let mut samples: Vec<f64> = spread(FIRST, LAST, even.max(2))
+		.chain(spread(low, high, resolution - even))
+		.collect();
+	samples.sort_by(|x, y| x.partial_cmp(y).unwrap());
+	samples.dedup_by(|x, y| (*x - *y).abs() < 1e-9);
+	samples
+}
+
+/// The density of the beta distribution with the given shape at `x`
+pub fn beta_pdf(a: f64, b: f64, x: f64) -> f64 {
+	if !(0.0..=1.0).contains(&x) {
+		return 0.0;
+	}
+	let ln_beta = ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b);
+	// a power of 0 is 1, even of 0
+	let ln_power = |base: f64, exponent: f64| {
+		if exponent == 0.0 {
+			0.0
+		} else {
+			exponent * base.ln()
+		}
+	};
+	(ln_power(x, a - 1.0) + ln_power(1.0 - x, b - 1.0) - ln_beta).exp()
+}
+
+/// The log of the gamma function by the Lanczos approximation, for `x` above 0. A prior can give
+/// shapes below 1/2, which are reflected
+fn ln_gamma(x: f64) -> f64 {
+	const G: f64 = 7.0;
+	const COEFFICIENTS: [f64; 9] = [
+		0.999_999_999_999_809_9,
+		676.520_368_121_885_1,
+		-1_259.139_216_722_402_8,
+		771.323_428_777_653_1,
+		-176.615_029_162_140_6,
+		12.507_343_278_686_905,
+		-0.138_571_095_265_720_12,
+		9.984_369_578_019_572e-6,
+		1.505_632_735_149_311_6e-7,
+	];
+	if x < 0.5 {
+		let pi = std::f64::consts::PI;
+		return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+	}
+	let x = x - 1.0;
+	let t = x + G + 0.5;
+	let sum = COEFFICIENTS[1..]
+		.iter()
+		.enumerate()
+		.fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0));
+	0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+fn render_ucb(state: &mut RenderedState, ucb: &UcbCountState) {
+	fn append(mean: f64, upper: f64, y_offset: f64) -> String {
+		if mean.is_nan() || upper.is_nan() {
+			return "M 0 0 L 0 0 Z".to_string();
+		}
+		format!("M {} {} L {} {} Z", mean, y_offset, upper, y_offset)
+	}
+	// spread the intervals evenly over the height of the graph
+	let spacing = 1.0 / (ucb.past.len() + 1) as f64;
+	state.ucb_paths = ucb
+		.intervals()
+		.iter()
+		.enumerate()
+		.map(|(i, (mean, upper))| append(*mean, *upper, spacing * (i + 1) as f64))
+		.collect();
+}
+
+fn render_text(
+	state: &mut RenderedState,
+	thompson: (usize, usize),
+	naive: (usize, usize),
+	ucb: (usize, usize),
+	player: (usize, usize),
+) {
+	state.thompson_counts = thompson;
+	state.naive_counts = naive;
+	state.ucb_counts = ucb;
+	state.player_counts = player;
+}
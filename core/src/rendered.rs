@@ -0,0 +1,144 @@
+use crate::{charts, coin_color, css_color, Chart, History, Panel};
+use serde::{Deserialize, Serialize};
+
+/// Output of the text CLI, loaded by the view page
+#[derive(Default, Serialize, Deserialize)]
+pub struct RenderedStateContainer {
+	#[allow(clippy::vec_box)]
+	// box so that no cloning is performed => huge performance boost
+	pub state: Vec<Box<RenderedState>>,
+	/// The first of the chosen players
+	pub best_player_name: String,
+	/// The number of coins
+	#[serde(default)]
+	pub coins: usize,
+	/// The id of each chosen player, in the same order as each state's `players`. Older rendered
+	/// dumps only have the best player
+	#[serde(default)]
+	pub player_names: Vec<String>,
+	/// The color of each coin, from the layout. Older rendered dumps use the usual palette
+	#[serde(default)]
+	pub colors: Vec<(u8, u8, u8)>,
+	/// Every step of the session, for the time series charts. Older rendered dumps have none
+	#[serde(default)]
+	pub history: Option<History>,
+	/// The layout's chart panels
+	#[serde(default)]
+	pub chart_panels: Vec<Panel>,
+}
+
+impl RenderedStateContainer {
+	pub fn new(length: usize, player_names: Vec<String>, coins: usize) -> Self {
+		Self {
+			state: vec![Box::default(); length],
+			best_player_name: player_names.first().cloned().unwrap_or_default(),
+			coins,
+			player_names,
+			colors: Vec::new(),
+			history: None,
+			chart_panels: Vec::new(),
+		}
+	}
+	/// The color of the given coin
+	pub fn color(&self, coin: usize) -> (u8, u8, u8) {
+		self.colors
+			.get(coin)
+			.copied()
+			.unwrap_or_else(|| coin_color(coin))
+	}
+	/// The color of the given coin as a css `rgb(r,g,b)` string
+	pub fn css_color(&self, coin: usize) -> String {
+		css_color(self.color(coin))
+	}
+	/// The time series charts, with the player at `player` for the player's lines
+	pub fn charts(&self, player: usize) -> Vec<Chart> {
+		self.history.as_ref().map_or_else(Vec::new, |history| {
+			charts(history, &self.chart_panels, player, |coin| self.color(coin))
+		})
+	}
+}
+
+/// The shapes to draw for a single step
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct RenderedState {
+	/// One stacked box per coin
+	pub thompson_rects: Vec<Rectangle>,
+	pub ucb_rects: Vec<Rectangle>,
+	pub naive_rects: Vec<Rectangle>,
+	pub player_rects: Vec<Rectangle>,
+	pub thompson_counts: (usize, usize),
+	pub ucb_counts: (usize, usize),
+	pub naive_counts: (usize, usize),
+	pub player_counts: (usize, usize),
+	/// One beta distribution per coin
+	pub thompson_paths: Vec<String>,
+	/// One confidence interval per coin
+	pub ucb_paths: Vec<String>,
+	/// One bar per coin, as long as the proportion of heads the naive strategy saw on it while
+	/// exploring
+	#[serde(default)]
+	pub naive_means: Vec<Rectangle>,
+	/// How many flips the naive strategy has explored for, and how many it explores for
+	#[serde(default)]
+	pub naive_explored: (usize, usize),
+	/// The coin the naive strategy settled on, once it has
+	#[serde(default)]
+	pub naive_committed: Option<usize>,
+	/// The boxes and counts of each chosen player. The first is also in `player_rects` and
+	/// `player_counts`
+	#[serde(default)]
+	pub players: Vec<RenderedPlayer>,
+	/// The step of the session shown, where the charts' cursors are
+	#[serde(default)]
+	pub step: usize,
+}
+
+/// What one player has flipped by some step
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct RenderedPlayer {
+	/// One stacked box per coin
+	pub rects: Vec<Rectangle>,
+	pub counts: (usize, usize),
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct Rectangle {
+	pub x: f64,
+	pub y: f64,
+	pub width: f64,
+	pub height: f64,
+}
+
+/// Where the naive strategy's bars are drawn in the default layout, which the view page draws the
+/// axes for
+pub const NAIVE_PANEL: Rectangle = Rectangle {
+	x: 31.75,
+	y: 251.0,
+	width: 142.875,
+	height: 36.0,
+};
+
+/// One bar per coin for the naive strategy's means, spread down the panel from its left edge with
+/// a bar as long as the panel for a mean of 1. Coins it has not flipped get no length
+pub fn naive_bars(panel: &Rectangle, means: &[Option<f64>]) -> Vec<Rectangle> {
+	let row = panel.height / means.len().max(1) as f64;
+	means
+		.iter()
+		.enumerate()
+		.map(|(coin, mean)| Rectangle {
+			x: panel.x,
+			y: panel.y + row * (coin as f64 + 0.2),
+			width: panel.width * mean.unwrap_or(0.0),
+			height: row * 0.6,
+		})
+		.collect()
+}
+
+/// What the naive strategy is doing, for the panel's title
+pub fn naive_status(explored: (usize, usize), committed: Option<usize>) -> String {
+	match committed {
+		Some(coin) => format!("Naive settled on coin {}", coin + 1),
+		None if explored.0 >= explored.1 => "Naive is done exploring".to_string(),
+		None => format!("Naive exploring, {}/{} flips", explored.0, explored.1),
+	}
+}
@@ -0,0 +1,268 @@
+use crate::FilteredData;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone)]
+pub struct GeneralState {
+	/// Count for each coin
+	pub count: Vec<usize>,
+	pub successes: usize,
+	pub failures: usize,
+}
+
+impl GeneralState {
+	fn new(num_coins: usize) -> Self {
+		Self {
+			count: vec![0; num_coins],
+			successes: 0,
+			failures: 0,
+		}
+	}
+}
+
+/// The shape of each coin's beta distribution, starting from the server's prior
+#[derive(Clone)]
+pub struct ThompsonBetaState {
+	pub a: Vec<f64>,
+	pub b: Vec<f64>,
+}
+
+impl ThompsonBetaState {
+	fn new(num_coins: usize, [a, b]: [f64; 2]) -> Self {
+		Self {
+			a: vec![a; num_coins],
+			b: vec![b; num_coins],
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct UcbCountState {
+	pub past: Vec<(usize, usize)>,
+	pub total_flips: usize,
+	/// How wide the confidence bound is
+	pub confidence: f64,
+}
+
+impl UcbCountState {
+	fn new(num_coins: usize, confidence: f64) -> Self {
+		UcbCountState {
+			past: vec![(0, 0); num_coins],
+			total_flips: 0,
+			confidence,
+		}
+	}
+	/// The proportion of heads on each coin and the top of its confidence bound. Both are NaN
+	/// until the coin is flipped
+	pub fn intervals(&self) -> Vec<(f64, f64)> {
+		self.past
+			.iter()
+			.map(|(heads, tails)| {
+				let mean = *heads as f64 / (heads + tails) as f64;
+				let upper = ucb_bound(*heads, *tails, self.total_flips, self.confidence);
+				(mean, upper)
+			})
+			.collect()
+	}
+}
+
+/// The top of UCB's confidence bound for a coin, the one it flips being the highest. NaN for a
+/// coin that has not been flipped, which UCB flips first
+pub fn ucb_bound(heads: usize, tails: usize, total_flips: usize, confidence: f64) -> f64 {
+	let flips = (heads + tails) as f64;
+	heads as f64 / flips + f64::sqrt(confidence * f64::log(total_flips as f64, 10.0) / flips)
+}
+
+/// What the naive strategy saw while exploring, and the coin it settled on
+#[derive(Clone)]
+pub struct NaiveMeanState {
+	/// The heads and flips on each coin during exploration
+	pub explored: Vec<(usize, usize)>,
+	/// How many flips exploration lasts
+	pub exploration: usize,
+	/// How many flips have been made
+	pub flips: usize,
+	/// The coin flipped once exploration ended
+	pub committed: Option<usize>,
+}
+
+impl NaiveMeanState {
+	fn new(num_coins: usize, exploration: usize) -> Self {
+		Self {
+			explored: vec![(0, 0); num_coins],
+			exploration,
+			flips: 0,
+			committed: None,
+		}
+	}
+	/// The proportion of heads on each coin during exploration, if it was flipped
+	pub fn means(&self) -> Vec<Option<f64>> {
+		self.explored
+			.iter()
+			.map(|(heads, flips)| match flips {
+				0 => None,
+				flips => Some(*heads as f64 / *flips as f64),
+			})
+			.collect()
+	}
+}
+
+/// Which steps of a dump to draw: every `stride`th step from `start` up to but not including
+/// `end`. The last of them is always drawn, so a stride never hides how the game ended
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Steps {
+	pub start: usize,
+	/// Up to the end of the dump if not given
+	pub end: Option<usize>,
+	pub stride: usize,
+}
+
+impl Default for Steps {
+	fn default() -> Self {
+		Steps {
+			start: 0,
+			end: None,
+			stride: 1,
+		}
+	}
+}
+
+impl Steps {
+	/// The first `count` steps
+	pub fn first(count: usize) -> Self {
+		Steps {
+			end: Some(count),
+			..Steps::default()
+		}
+	}
+	/// The steps to draw out of a dump `length` steps long, in order
+	pub fn select(&self, length: usize) -> Vec<usize> {
+		let end = self.end.map_or(length, |end| end.min(length));
+		if self.start >= end {
+			return Vec::new();
+		}
+		let mut steps: Vec<usize> = (self.start..end).step_by(self.stride.max(1)).collect();
+		if steps.last() != Some(&(end - 1)) {
+			steps.push(end - 1);
+		}
+		steps
+	}
+}
+
+/// What each algorithm and each chosen player have seen up to some step
+#[derive(Clone)]
+pub struct RenderState {
+	pub thompson: (GeneralState, ThompsonBetaState),
+	pub ucb: (GeneralState, UcbCountState),
+	pub naive: (GeneralState, NaiveMeanState),
+	/// One for each chosen player, in order
+	pub players: Vec<GeneralState>,
+}
+
+impl RenderState {
+	pub fn new(data: &FilteredData) -> Self {
+		let num_coins = data.coins;
+		Self {
+			thompson: (
+				GeneralState::new(num_coins),
+				ThompsonBetaState::new(num_coins, data.prior),
+			),
+			ucb: (
+				GeneralState::new(num_coins),
+				UcbCountState::new(num_coins, data.confidence),
+			),
+			naive: (
+				GeneralState::new(num_coins),
+				NaiveMeanState::new(num_coins, data.exploration),
+			),
+			players: vec![GeneralState::new(num_coins); data.players.len()],
+		}
+	}
+	pub fn update(&mut self, data: &FilteredData, index: usize) {
+		if let Some((coin, result)) = data.thompson.get(index) {
+			self.thompson.0.count[*coin] += 1;
+			self.thompson.0.successes += if *result { 1 } else { 0 };
+			self.thompson.0.failures += if *result { 0 } else { 1 };
+			self.thompson.1.a[*coin] += if *result { 1.0 } else { 0.0 };
+			self.thompson.1.b[*coin] += if *result { 0.0 } else { 1.0 };
+		};
+		if let Some((coin, result)) = data.naive.get(index) {
+			self.naive.0.count[*coin] += 1;
+			self.naive.0.successes += if *result { 1 } else { 0 };
+			self.naive.0.failures += if *result { 0 } else { 1 };
+			let naive = &mut self.naive.1;
+			if naive.flips < naive.exploration {
+				naive.explored[*coin].0 += if *result { 1 } else { 0 };
+				naive.explored[*coin].1 += 1;
+			} else if naive.committed.is_none() {
+				naive.committed = Some(*coin);
+			}
+			naive.flips += 1;
+		};
+		if let Some((coin, result)) = data.ucb.get(index) {
+			self.ucb.0.count[*coin] += 1;
+			self.ucb.1.total_flips += 1;
+			if *result {
+				self.ucb.0.successes += 1;
+				self.ucb.1.past[*coin].0 += 1;
+			} else {
+				self.ucb.0.failures += 1;
+				self.ucb.1.past[*coin].1 += 1;
+			}
+		};
+		for (player, (_, past)) in self.players.iter_mut().zip(data.players.iter()) {
+			if let Some((coin, result)) = past.get(index) {
+				player.count[*coin] += 1;
+				player.successes += if *result { 1 } else { 0 };
+				player.failures += if *result { 0 } else { 1 };
+			};
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn selects_every_step_by_default() {
+		assert_eq!(Steps::default().select(4), vec![0, 1, 2, 3]);
+		assert_eq!(Steps::default().select(0), Vec::<usize>::new());
+	}
+
+	#[test]
+	fn always_draws_the_last_step() {
+		let steps = Steps {
+			start: 1,
+			end: None,
+			stride: 3,
+		};
+		assert_eq!(steps.select(9), vec![1, 4, 7, 8]);
+		assert_eq!(steps.select(8), vec![1, 4, 7]);
+	}
+
+	#[test]
+	fn stops_at_the_end_of_the_dump() {
+		assert_eq!(Steps::first(3).select(10), vec![0, 1, 2]);
+		assert_eq!(Steps::first(10).select(3), vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn selects_nothing_past_the_end() {
+		let steps = Steps {
+			start: 5,
+			end: Some(5),
+			stride: 1,
+		};
+		assert!(steps.select(10).is_empty());
+		assert!(Steps { start: 5, ..steps }.select(3).is_empty());
+	}
+
+	#[test]
+	fn treats_a_zero_stride_as_one() {
+		let steps = Steps {
+			stride: 0,
+			..Steps::default()
+		};
+		assert_eq!(steps.select(3), vec![0, 1, 2]);
+	}
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// How a player did compared to the algorithms over the same number of flips.
+/// Served at `/summary` once a player has used up their flips.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Summary {
+	/// How many flips the player made
+	pub flips: usize,
+	/// How many of the player's flips were heads
+	pub heads: usize,
+	/// How many heads each algorithm got in its first `flips` flips
+	pub strategies: Vec<StrategySummary>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StrategySummary {
+	pub name: String,
+	pub heads: usize,
+}
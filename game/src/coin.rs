@@ -1,3 +1,4 @@
+use coins_core::{api::Flip, ErrorBody};
 use mogwai::prelude::*;
 use web_sys::{Request, RequestInit, RequestMode, Response};
 
@@ -11,7 +12,11 @@ pub enum CoinIn {
 	/// The coin is clicked. Make a request to get the flip result, then animate and notify parent.
 	Clicked,
 	/// Response from the server.
-	Response(bool),
+	Response(Flip),
+	/// The server refused the flip because the player has no flips left.
+	Exhausted,
+	/// The server refused the flip because the round is over.
+	RoundOver,
 	/// No action.
 	None,
 }
@@ -19,7 +24,11 @@ pub enum CoinIn {
 #[derive(Clone)]
 pub enum CoinOut {
 	/// Tell the parent the result of a coin flip.
-	Flipped(bool),
+	Flipped(Flip),
+	/// Tell the parent that the player has no flips left.
+	Exhausted,
+	/// Tell the parent that the round is over.
+	RoundOver,
 }
 
 impl Component for Coin {
@@ -30,10 +39,10 @@ impl Component for Coin {
 	fn update(&mut self, msg: &CoinIn, tx: &Transmitter<CoinOut>, subscriber: &Subscriber<CoinIn>) {
 		match msg {
 			CoinIn::Clicked => {
-				let mut opts = RequestInit::new();
-				opts.method("GET");
-				opts.mode(RequestMode::SameOrigin);
-				let url = format!("/flip/{}", self.arm);
+				let opts = RequestInit::new();
+				opts.set_method("POST");
+				opts.set_mode(RequestMode::SameOrigin);
+				let url = format!("/api/v1/flip/{}", self.arm);
 				// create an async request. send, take response, and send it on the transmitter
 				// subscribe to the receiver via subscriber, then act on the response
 				let (tx_req, rx_req): (Transmitter<CoinIn>, Receiver<CoinIn>) = txrx();
@@ -47,19 +56,39 @@ impl Component for Coin {
 						.dyn_into::<Response>()
 						.expect("Malformed response");
 					if response.status() == 200 {
-						let result = JsFuture::from(response.text().expect("Malformed response"))
+						JsFuture::from(response.text().expect("Malformed response"))
 							.await
 							.expect("Malformed response")
-							== "true";
-						CoinIn::Response(result)
+							.as_string()
+							.and_then(|s| serde_json::from_str::<Flip>(&s).ok())
+							.map(CoinIn::Response)
+							.unwrap_or(CoinIn::None)
+					} else if response.status() == 403 {
+						// both an exhausted budget and a finished round are forbidden, the code says which
+						let body = JsFuture::from(response.text().expect("Malformed response"))
+							.await
+							.expect("Malformed response")
+							.as_string()
+							.and_then(|s| serde_json::from_str::<ErrorBody>(&s).ok());
+						match body.as_ref().map(|body| body.code.as_str()) {
+							Some("no_flips_left") => CoinIn::Exhausted,
+							Some("round_over") => CoinIn::RoundOver,
+							_ => CoinIn::None,
+						}
 					} else {
 						CoinIn::None
 					}
 				});
 				subscriber.subscribe(&rx_req);
 			}
-			CoinIn::Response(result) => {
-				tx.send(&CoinOut::Flipped(*result));
+			CoinIn::Response(flip) => {
+				tx.send(&CoinOut::Flipped(flip.clone()));
+			}
+			CoinIn::Exhausted => {
+				tx.send(&CoinOut::Exhausted);
+			}
+			CoinIn::RoundOver => {
+				tx.send(&CoinOut::RoundOver);
 			}
 			CoinIn::None => {}
 		}
@@ -71,8 +100,9 @@ impl Component for Coin {
 		let (tx_color, rx_color) = txrx();
 		rx.branch_map(|msg: &CoinOut| {
 			match msg {
-				CoinOut::Flipped(true) => "green",
-				CoinOut::Flipped(false) => "firebrick",
+				CoinOut::Flipped(Flip { result: true, .. }) => "green",
+				CoinOut::Flipped(Flip { result: false, .. }) => "firebrick",
+				CoinOut::Exhausted | CoinOut::RoundOver => "grey",
 			}
 			.to_string()
 		})
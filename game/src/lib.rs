@@ -1,3 +1,5 @@
+use coins_core::api::{Flip, Player};
+use coins_core::{GameConfig, Summary};
 use log::Level;
 use mogwai::prelude::*;
 use std::panic;
@@ -24,20 +26,35 @@ macro_rules! console_log {
 
 struct Game {
 	username: String,
+	/// The rules of the game, once loaded from the server
+	config: Option<GameConfig>,
 	count: i32,
 	coins: Vec<Gizmo<coin::Coin>>,
 	next_id: usize,
 	audios: Vec<Gizmo<audio::Audio>>,
+	/// Whether the countdown to the end of the round is running
+	ticking: bool,
 }
 
 #[derive(Clone)]
 enum GameIn {
 	/// From the coin models, result of a coin flip
-	Flipped(bool),
+	Flipped(Flip),
 	/// From main, triggers a request to check current count
 	Load,
-	/// Loaded. Containes the count, newline, id
-	Loaded(String),
+	/// Loaded. Contains the player's score and flips
+	Loaded(Player),
+	/// The rules of the game from the server
+	LoadedConfig(GameConfig),
+	/// The player has no flips left, from their last flip or a refused one. Triggers a request for
+	/// the summary
+	Exhausted,
+	/// From the coin models, the round is over. Triggers a reload to pick up the next round
+	RoundOver,
+	/// How the player did against the algorithms
+	LoadedSummary(Summary),
+	/// Update the countdown to the end of the round
+	Tick,
 	/// Create a new item
 	NewAudio(bool),
 	/// Remove the item at the given index
@@ -49,10 +66,20 @@ enum GameIn {
 enum GameOut {
 	/// Send to the view the new count
 	Count(i32),
-	/// Send to the view the new count and the new id
+	/// Send to the view the player's name
 	LoadedId(String),
+	/// Send to the view how many flips are left, if limited
+	FlipsLeft(String),
 	/// Patch the view of audios
 	PatchAudio(Patch<View<HtmlElement>>),
+	/// Patch the view of coins
+	PatchCoins(Patch<View<HtmlElement>>),
+	/// Send to the view the name of the room
+	Room(String),
+	/// Send to the view how the player did against the algorithms
+	Summary(String),
+	/// Send to the view the time left in the round
+	Countdown(String),
 }
 
 impl Component for Game {
@@ -60,6 +87,7 @@ impl Component for Game {
 	type ViewMsg = GameOut;
 	type DomNode = HtmlElement;
 
+	#[allow(unused_braces)]
 	fn update(
 		&mut self,
 		msg: &GameIn,
@@ -67,19 +95,156 @@ impl Component for Game {
 		subscriber: &Subscriber<GameIn>,
 	) {
 		match msg {
-			GameIn::Flipped(flipped) => {
-				if *flipped {
-					self.count += 1;
-				} else {
-					self.count -= 1;
-				}
+			GameIn::Flipped(flip) => {
+				self.count = flip.score;
 				tx_view.send(&GameOut::Count(self.count));
+				tx_view.send(&GameOut::FlipsLeft(flips_left(flip.flips_left)));
+				if flip.flips_left == Some(0) {
+					// the last flip of the budget, so there is no need to wait for a refused one
+					subscriber.send_async(async { GameIn::Exhausted });
+				}
 			}
 			GameIn::Load => {
-				let mut opts = RequestInit::new();
-				opts.method("GET");
-				opts.mode(RequestMode::SameOrigin);
-				let req = Request::new_with_str_and_init("/count", &opts)
+				let opts = RequestInit::new();
+				opts.set_method("GET");
+				opts.set_mode(RequestMode::SameOrigin);
+				let req = Request::new_with_str_and_init("/api/v1/player", &opts)
+					.expect("Failed to create request");
+				let (tx, rx) = txrx();
+				tx.send_async(async move {
+					let window = web_sys::window().unwrap();
+					let response = JsFuture::from(window.fetch_with_request(&req))
+						.await
+						.expect("Failed to send request")
+						.dyn_into::<Response>()
+						.expect("Malformed response");
+					if response.status() == 200 {
+						JsFuture::from(response.text().unwrap())
+							.await
+							.unwrap()
+							.as_string()
+							.and_then(|s| serde_json::from_str::<Player>(&s).ok())
+							.map(GameIn::Loaded)
+							.unwrap_or(GameIn::None)
+					} else if response.status() == 401 || response.status() == 404 {
+						// not signed in, or the session is no longer valid
+						window.location().set_href("/").expect("Unable to redirect");
+						GameIn::None
+					} else {
+						GameIn::None
+					}
+				});
+				subscriber.subscribe(&rx);
+
+				let req = Request::new_with_str_and_init("/api/v1/config", &opts)
+					.expect("Failed to create request");
+				let (tx, rx) = txrx();
+				tx.send_async(async move {
+					let window = web_sys::window().unwrap();
+					let response = JsFuture::from(window.fetch_with_request(&req))
+						.await
+						.expect("Failed to send request")
+						.dyn_into::<Response>()
+						.expect("Malformed response");
+					if response.status() == 200 {
+						JsFuture::from(response.text().unwrap())
+							.await
+							.unwrap()
+							.as_string()
+							.and_then(|s| serde_json::from_str::<GameConfig>(&s).ok())
+							.map(GameIn::LoadedConfig)
+							.unwrap_or(GameIn::None)
+					} else {
+						GameIn::None
+					}
+				});
+				subscriber.subscribe(&rx);
+			}
+			GameIn::LoadedConfig(config) => {
+				tx_view.send(&GameOut::Room(match config.flip_budget {
+					Some(budget) => format!("{} - {} flips each", config.room, budget),
+					None => config.room.clone(),
+				}));
+				for (arm, coin_config) in config.coins.iter().enumerate().skip(self.coins.len()) {
+					let gizmo = Gizmo::from(coin::Coin { arm });
+					subscriber.subscribe_map(&gizmo.recv, |m: &coin::CoinOut| match m {
+						coin::CoinOut::Flipped(flip) => GameIn::Flipped(flip.clone()),
+						coin::CoinOut::Exhausted => GameIn::Exhausted,
+						coin::CoinOut::RoundOver => GameIn::RoundOver,
+					});
+					subscriber.subscribe_filter_map(&gizmo.recv, |m: &coin::CoinOut| match m {
+						coin::CoinOut::Flipped(flip) => Some(GameIn::NewAudio(flip.result)),
+						coin::CoinOut::Exhausted | coin::CoinOut::RoundOver => None,
+					});
+					let view: View<HtmlElement> = View::from(builder!(
+						<div class="col">
+							{gizmo.view_builder()}
+							<p class="text-center" style={format!("color: {};", coin_config.color)}>{coin_config.label.clone()}</p>
+						</div>
+					));
+					tx_view.send(&GameOut::PatchCoins(Patch::PushBack { value: view }));
+					self.coins.push(gizmo);
+				}
+				if matches!(&self.config, Some(previous) if previous.round != config.round) {
+					// the server starts every round with a clean slate
+					tx_view.send(&GameOut::Summary(String::new()));
+				}
+				if config.round_end.is_some() && !self.ticking {
+					self.ticking = true;
+					subscriber.send_async(async { GameIn::Tick });
+				}
+				self.config = Some(config.clone());
+			}
+			GameIn::Tick => {
+				let (round, round_end) = match &self.config {
+					Some(GameConfig {
+						round,
+						round_end: Some(round_end),
+						..
+					}) => (*round, *round_end),
+					_ => {
+						self.ticking = false;
+						return;
+					}
+				};
+				let seconds = ((round_end as f64 - js_sys::Date::now()) / 1000.0).ceil();
+				if seconds > 0.0 {
+					let seconds = seconds as u64;
+					tx_view.send(&GameOut::Countdown(format!(
+						"Round {} - {}:{:02} left",
+						round,
+						seconds / 60,
+						seconds % 60
+					)));
+					subscriber.send_async(async {
+						utils::wait_approximately(1000.0).await;
+						GameIn::Tick
+					});
+				} else {
+					// give the server a moment to start the next round, then pick it up
+					tx_view.send(&GameOut::Countdown(format!("Round {} is over", round)));
+					self.ticking = false;
+					subscriber.send_async(async {
+						utils::wait_approximately(1000.0).await;
+						GameIn::Load
+					});
+				}
+			}
+			GameIn::RoundOver => {
+				// the flip came in after the round ended, so the budget is not what ran out
+				if let Some(config) = &self.config {
+					tx_view.send(&GameOut::Countdown(format!("Round {} is over", config.round)));
+				}
+				subscriber.send_async(async {
+					utils::wait_approximately(1000.0).await;
+					GameIn::Load
+				});
+			}
+			GameIn::Exhausted => {
+				let opts = RequestInit::new();
+				opts.set_method("GET");
+				opts.set_mode(RequestMode::SameOrigin);
+				let req = Request::new_with_str_and_init("/api/v1/summary", &opts)
 					.expect("Failed to create request");
 				let (tx, rx) = txrx();
 				tx.send_async(async move {
@@ -90,26 +255,41 @@ impl Component for Game {
 						.dyn_into::<Response>()
 						.expect("Malformed response");
 					if response.status() == 200 {
-						console_log!("Successfully set cookie, response: {:?}", response);
-						GameIn::Loaded(
-							JsFuture::from(response.text().unwrap())
-								.await
-								.unwrap()
-								.as_string()
-								.unwrap(),
-						)
+						JsFuture::from(response.text().unwrap())
+							.await
+							.unwrap()
+							.as_string()
+							.and_then(|s| serde_json::from_str::<Summary>(&s).ok())
+							.map(GameIn::LoadedSummary)
+							.unwrap_or(GameIn::None)
 					} else {
 						GameIn::None
 					}
 				});
 				subscriber.subscribe(&rx);
 			}
-			GameIn::Loaded(string) => {
-				let split: Vec<&str> = string.splitn(2, '\n').collect();
-				self.count = split[0].parse::<i32>().unwrap();
-				self.username = split[1].to_string();
+			GameIn::LoadedSummary(summary) => {
+				let strategies = summary
+					.strategies
+					.iter()
+					.map(|s| format!("{} got {}", s.name, s.heads))
+					.collect::<Vec<String>>()
+					.join(", ");
+				tx_view.send(&GameOut::Summary(format!(
+					"No flips left! You got {} heads in {} flips. Over the same {} flips: {}.",
+					summary.heads, summary.flips, summary.flips, strategies
+				)));
+			}
+			GameIn::Loaded(player) => {
+				console_log!("Loaded player {}", player.id);
+				self.count = player.score;
+				self.username = player.name.clone();
 				tx_view.send(&GameOut::LoadedId(self.username.clone()));
 				tx_view.send(&GameOut::Count(self.count));
+				tx_view.send(&GameOut::FlipsLeft(flips_left(player.flips_left)));
+				if player.flips_left == Some(0) {
+					subscriber.send_async(async { GameIn::Exhausted });
+				}
 			}
 			GameIn::NewAudio(yes) => {
 				let item = audio::Audio {
@@ -147,41 +327,44 @@ impl Component for Game {
 	}
 
 	#[allow(unused_braces)]
-	fn view(&self, tx: &Transmitter<GameIn>, rx: &Receiver<GameOut>) -> ViewBuilder<HtmlElement> {
+	fn view(&self, _tx: &Transmitter<GameIn>, rx: &Receiver<GameOut>) -> ViewBuilder<HtmlElement> {
 		let rx_count = rx.branch_filter_map(|msg: &GameOut| match msg {
 			GameOut::Count(count) => Some(format!("{}", count)),
 			_ => None,
 		});
-		self.coins.iter().for_each(|g| {
-			g.recv.clone().forward_filter_map(tx, |m: &coin::CoinOut| {
-				let coin::CoinOut::Flipped(flipped) = m;
-				Some(GameIn::Flipped(*flipped))
-			});
-			g.recv.clone().forward_filter_map(tx, |m: &coin::CoinOut| {
-				let coin::CoinOut::Flipped(flipped) = m;
-				Some(GameIn::NewAudio(*flipped))
-			});
-		});
-
 		let rx_load = rx.branch_filter_map(|msg: &GameOut| match msg {
 			GameOut::LoadedId(string) => Some(string.to_string()),
 			_ => None,
 		});
 
+		let rx_flips_left = rx.branch_filter_map(|msg: &GameOut| match msg {
+			GameOut::FlipsLeft(flips_left) => Some(flips_left.to_string()),
+			_ => None,
+		});
+
+		let rx_room = rx.branch_filter_map(|msg: &GameOut| match msg {
+			GameOut::Room(room) => Some(room.to_string()),
+			_ => None,
+		});
+		let rx_countdown = rx.branch_filter_map(|msg: &GameOut| match msg {
+			GameOut::Countdown(countdown) => Some(countdown.to_string()),
+			_ => None,
+		});
+		let rx_summary = rx.branch_filter_map(|msg: &GameOut| match msg {
+			GameOut::Summary(summary) => Some(summary.to_string()),
+			_ => None,
+		});
+
 		builder!(
 		<div class="container">
+			<p class="text-center">{("", rx_room)}</p>
+			<p class="text-center">{("", rx_countdown)}</p>
 			<h1 class="text-center" style="pointer-events: none;">{("0", rx_count)}</h1>
 			<p class="text-center">{("", rx_load)}</p>
-			<div class="row row-cols-1 row-cols-md-3 row-cols-lg-3 row-cols-xl-3 row-cols-xxl-3">
-				<div class="col">
-					{self.coins[0].view_builder()}
-				</div>
-				<div class="col">
-					{self.coins[1].view_builder()}
-				</div>
-				<div class="col">
-					{self.coins[2].view_builder()}
-				</div>
+			<p class="text-center">{("", rx_flips_left)}</p>
+			<p class="text-center">{("", rx_summary)}</p>
+			// container for coins, filled in once the server says how many there are
+			<div class="row row-cols-1 row-cols-md-3 row-cols-lg-3 row-cols-xl-3 row-cols-xxl-3" patch:children=rx.branch_filter_map(|m: &GameOut | if let GameOut::PatchCoins(patch) = m { Some(patch.clone())} else { None} )>
 			</div>
 			// container for audios
 			<div patch:children=rx.branch_filter_map(|m: &GameOut | if let GameOut::PatchAudio(patch) = m { Some(patch.clone())} else { None} )>
@@ -191,6 +374,15 @@ impl Component for Game {
 	}
 }
 
+/// What to show for the number of flips left
+fn flips_left(flips_left: Option<usize>) -> String {
+	match flips_left {
+		Some(1) => "1 flip left".to_string(),
+		Some(flips_left) => format!("{} flips left", flips_left),
+		None => String::new(),
+	}
+}
+
 #[wasm_bindgen]
 pub fn main(parent_id: Option<String>) -> Result<(), JsValue> {
 	panic::set_hook(Box::new(console_error_panic_hook::hook));
@@ -198,13 +390,12 @@ pub fn main(parent_id: Option<String>) -> Result<(), JsValue> {
 
 	let gizmo = Gizmo::from(Game {
 		username: "".to_string(),
+		config: None,
 		count: 0,
-		coins: (0..3)
-			.map(|i| coin::Coin { arm: i })
-			.map(Gizmo::from)
-			.collect(),
+		coins: Vec::new(),
 		next_id: 0,
 		audios: Vec::new(),
+		ticking: false,
 	});
 	let view = View::from(gizmo.view_builder());
 	gizmo.send(&GameIn::Load);
@@ -1,3 +1,5 @@
+use coins_core::api::LoginRequest;
+use coins_core::ErrorBody;
 use log::Level;
 use mogwai::prelude::*;
 use std::panic;
@@ -5,7 +7,7 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{console::log, HtmlInputElement, KeyboardEvent};
-use web_sys::{Request, RequestInit, RequestMode, Response};
+use web_sys::{Headers, Request, RequestInit, RequestMode, Response};
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -28,11 +30,16 @@ pub enum LoginIn {
 	Update(String),
 	Submit,
 	GotCookie,
+	/// The server turned the username down, with the reason to show
+	Rejected(String),
 	None,
 }
 
 #[derive(Clone)]
-pub enum LoginOut {}
+pub enum LoginOut {
+	/// Show why the username was turned down. Empty to clear it
+	Error(String),
+}
 
 impl Component for Login {
 	type ModelMsg = LoginIn;
@@ -42,27 +49,37 @@ impl Component for Login {
 	fn update(
 		&mut self,
 		msg: &LoginIn,
-		_tx_view: &Transmitter<LoginOut>,
+		tx_view: &Transmitter<LoginOut>,
 		subscriber: &Subscriber<LoginIn>,
 	) {
 		match msg {
 			LoginIn::Update(new_name) => {
 				self.username = new_name.to_string();
+				tx_view.send(&LoginOut::Error(String::new()));
 			}
 			LoginIn::Submit => {
-				use urlencoding::encode;
 				console_log!("Submitting username: {}", self.username);
-				let mut opts = RequestInit::new();
-				opts.method("GET");
-				opts.mode(RequestMode::SameOrigin);
-				let url = format!("/cookie/{}", encode(&self.username));
+				let body = serde_json::to_string(&LoginRequest {
+					username: self.username.clone(),
+				})
+				.expect("Failed to serialize login");
+				let headers = Headers::new().expect("Failed to create headers");
+				headers
+					.set("Content-Type", "application/json")
+					.expect("Failed to set content type");
+				let opts = RequestInit::new();
+				opts.set_method("POST");
+				opts.set_mode(RequestMode::SameOrigin);
+				opts.set_headers(&headers);
+				opts.set_body(&JsValue::from_str(&body));
+				let url = "/api/v1/login";
 				// create an async request. send, take response, and send it on the transmitter
 				// subscribe to the receiver via subscriber, then act on the response
 				let (tx, rx) = txrx();
 				tx.send_async(async move {
 					let window = web_sys::window().unwrap();
 					let request =
-						Request::new_with_str_and_init(&url, &opts).expect("Should be valid URL");
+						Request::new_with_str_and_init(url, &opts).expect("Should be valid URL");
 					let response = JsFuture::from(window.fetch_with_request(&request))
 						.await
 						.expect("Failed to send request")
@@ -72,7 +89,13 @@ impl Component for Login {
 						console_log!("Successfully set cookie, response: {:?}", response);
 						LoginIn::GotCookie
 					} else {
-						LoginIn::None
+						JsFuture::from(response.text().unwrap())
+							.await
+							.ok()
+							.and_then(|text| text.as_string())
+							.and_then(|s| serde_json::from_str::<ErrorBody>(&s).ok())
+							.map(|error| LoginIn::Rejected(error.message))
+							.unwrap_or(LoginIn::None)
 					}
 				});
 				subscriber.subscribe(&rx);
@@ -82,16 +105,20 @@ impl Component for Login {
 				let location = window.location();
 				location.set_href("/redirect").expect("Unable to redirect");
 			}
+			LoginIn::Rejected(message) => {
+				tx_view.send(&LoginOut::Error(message.clone()));
+			}
 			LoginIn::None => {}
 		}
 	}
 
 	// Notice that the `Component::view` function returns a `ViewBuilder<T>` and not
 	// a `View<T>`.
+	#[allow(unused_braces)]
 	fn view(
 		&self,
 		tx: &Transmitter<LoginIn>,
-		_rx: &Receiver<LoginOut>,
+		rx: &Receiver<LoginOut>,
 	) -> ViewBuilder<HtmlElement> {
 		let tx_update = tx.contra_map(|e: &Event| {
 			LoginIn::Update(
@@ -112,13 +139,18 @@ impl Component for Login {
 			}
 		});
 
+		let rx_error = rx.branch_map(|msg: &LoginOut| match msg {
+			LoginOut::Error(message) => message.clone(),
+		});
+
 		builder!(
 			<div class="container d-md-flex justify-content-md-center align-items-md-center">
-				<div class="d-md-flex justify-content-md-center align-items-md-center" style="width: 50vw;height: 50vw;position: absolute;top: 50%;left: 50%;margin-left: -25vw;margin-top: -25vw;" onsubmit="return false">
+				<div class="d-md-flex flex-column justify-content-md-center align-items-md-center" style="width: 50vw;height: 50vw;position: absolute;top: 50%;left: 50%;margin-left: -25vw;margin-top: -25vw;" onsubmit="return false">
 					<form class="d-md-flex justify-content-center">
 						<input on:input=tx_update on:keydown=tx_enter class="form-control" type="text" placeholder="username" name="username" autofocus="" autocomplete="on" required="" style="border-right-color: #00000000;"></input>
 						<button on:click=tx_submit class="btn btn-primary" type="button">"enter"</button>
 					</form>
+					<p class="text-danger text-center">{("", rx_error)}</p>
 				</div>
 			</div>
 		)
@@ -0,0 +1,41 @@
+use crate::config::Admin;
+use crate::error::ApiError;
+use actix_web::{http, HttpRequest};
+
+/// Guards flushing and the view page with HTTP basic authentication, when an admin is configured
+pub struct Credentials {
+	admin: Option<Admin>,
+}
+
+impl Credentials {
+	pub fn new(admin: Option<Admin>) -> Self {
+		Credentials { admin }
+	}
+	/// Anyone passes if there is no admin
+	pub fn check(&self, req: &HttpRequest) -> Result<(), ApiError> {
+		let admin = match &self.admin {
+			Some(admin) => admin,
+			None => return Ok(()),
+		};
+		let given = req
+			.headers()
+			.get(http::header::AUTHORIZATION)
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.strip_prefix("Basic "))
+			.and_then(|value| base64::decode(value.trim()).ok())
+			.and_then(|value| String::from_utf8(value).ok());
+		match given.as_deref().and_then(|given| given.split_once(':')) {
+			Some((username, password))
+				if same(username, &admin.username) & same(password, &admin.password) =>
+			{
+				Ok(())
+			}
+			_ => Err(ApiError::AdminOnly),
+		}
+	}
+}
+
+/// Compare every byte, so how long it takes does not give away how much of a guess was right
+fn same(a: &str, b: &str) -> bool {
+	a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
@@ -0,0 +1,161 @@
+//! Handlers for the versioned JSON api under `/api/v1`, described by `openapi.json`
+use crate::error::ApiError;
+use crate::handlers::ApplicationState;
+use crate::rate_limit::RateLimit;
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use coins_core::api::{Login, LoginRequest};
+use coins_core::render::{render_dump, RESOLUTION_OF_DISTRIBUTION_SAMPLING};
+use coins_core::{CompactRendered, Layout, Metric, Ranking, Steps};
+use serde::Deserialize;
+
+/// Everything under `/api/v1`. Flipping is rate limited
+pub fn scope(rate_limit: RateLimit) -> actix_web::Scope {
+	web::scope("/api/v1")
+		.app_data(
+			web::JsonConfig::default()
+				.error_handler(|e, _| ApiError::BadRequest(e.to_string()).into()),
+		)
+		.app_data(
+			web::QueryConfig::default()
+				.error_handler(|e, _| ApiError::BadRequest(e.to_string()).into()),
+		)
+		.service(openapi)
+		.service(login)
+		.service(config)
+		.service(player)
+		.service(
+			web::resource("/flip/{coin}")
+				.wrap(rate_limit)
+				.route(web::post().to(flip)),
+		)
+		.service(summary)
+		.service(rendered)
+		.default_service(web::route().to(|| async { Err::<HttpResponse, _>(ApiError::NotFound) }))
+}
+
+/// The OpenAPI description of this api
+#[get("/openapi.json")]
+pub async fn openapi() -> HttpResponse {
+	HttpResponse::Ok()
+		.content_type("application/json")
+		.body(include_str!("../openapi.json"))
+}
+
+/// Set a signed cookie for 2 hours involving a uuid and the chosen name, once the name passes
+/// the username rules and nobody else in the room has it.
+/// Will overwrite any existing cookie.
+#[post("/login")]
+pub async fn login(
+	req: HttpRequest,
+	body: web::Json<LoginRequest>,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse, ApiError> {
+	use crate::app::LogIn;
+	let name = body.username.trim();
+	app_data.usernames.check(name)?;
+	let id = app_data
+		.send(LogIn {
+			name: name.to_string(),
+			previous: app_data.sessions.player_id(&req),
+		})
+		.await??;
+	let name = id.split_once('_').map_or(name, |(_, name)| name).to_string();
+	Ok(HttpResponse::Ok()
+		.cookie(app_data.sessions.cookie(id.clone()))
+		.json(Login { id, name }))
+}
+
+/// The rules of the game, so the game page follows the command line without being rebuilt
+#[get("/config")]
+pub async fn config(app_data: web::Data<ApplicationState>) -> Result<HttpResponse, ApiError> {
+	use crate::app::GetRound;
+	let round = app_data.send(GetRound {}).await?;
+	Ok(HttpResponse::Ok().json(app_data.game_config(&round)))
+}
+
+/// The player's score and flips this round
+#[get("/player")]
+pub async fn player(
+	req: HttpRequest,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse, ApiError> {
+	use crate::app::GetPlayer;
+	let id = app_data.sessions.player_id(&req).ok_or(ApiError::Unauthorized)?;
+	let player = app_data.send(GetPlayer { id }).await??;
+	Ok(HttpResponse::Ok().json(player))
+}
+
+/// Ask the application to flip a coin for the player
+pub async fn flip(
+	req: HttpRequest,
+	coin: web::Path<String>,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse, ApiError> {
+	use crate::app::FlipCoin;
+	let user_id = app_data.sessions.player_id(&req).ok_or(ApiError::Unauthorized)?;
+	let arm = coin
+		.parse::<usize>()
+		.map_err(|_| ApiError::InvalidCoin(coin.into_inner()))?;
+	let flip = app_data.send(FlipCoin { user_id, arm }).await??;
+	Ok(HttpResponse::Ok().json(flip))
+}
+
+/// How the player did against the algorithms over the same number of flips
+#[get("/summary")]
+pub async fn summary(
+	req: HttpRequest,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse, ApiError> {
+	use crate::app::GetSummary;
+	let id = app_data.sessions.player_id(&req).ok_or(ApiError::Unauthorized)?;
+	let summary = app_data.send(GetSummary { id }).await??;
+	Ok(HttpResponse::Ok().json(summary))
+}
+
+#[derive(Deserialize)]
+pub struct RenderedQuery {
+	/// Render at most this many steps
+	steps: Option<usize>,
+	/// What the players are ranked by
+	metric: Option<Metric>,
+	/// Players with fewer flips rank below everyone with enough
+	min_flips: Option<usize>,
+	/// Send the flips for the view to replay, instead of the shapes for every step
+	#[serde(default)]
+	compact: bool,
+}
+
+/// The round so far rendered for the view page, the same as the text CLI would from a flush, with
+/// every player who was not flagged, best first
+#[get("/rendered")]
+pub async fn rendered(
+	req: HttpRequest,
+	query: web::Query<RenderedQuery>,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse, ApiError> {
+	use crate::app::GetDump;
+	app_data.admin.check(&req)?;
+	let dump = app_data.send(GetDump {}).await?;
+	let steps = query.steps.map_or_else(Steps::default, Steps::first);
+	let default = Ranking::default();
+	let ranking = Ranking {
+		metric: query.metric.unwrap_or(default.metric),
+		min_flips: query.min_flips.unwrap_or(default.min_flips),
+	};
+	// rendering every step of a long round takes a while, so keep it off the server's threads
+	let compact = query.compact;
+	let body = web::block(move || {
+		let data = dump.filter(&dump.rank(&ranking))?;
+		let resolution = RESOLUTION_OF_DISTRIBUTION_SAMPLING;
+		let body = if compact {
+			let rendered = CompactRendered::new(data, steps, resolution, &Layout::default());
+			serde_cbor::to_vec(&rendered)
+		} else {
+			let rendered = render_dump(&data, &steps, resolution, &Layout::default());
+			serde_cbor::to_vec(&rendered)
+		};
+		body.map_err(|e| e.to_string())
+	})
+	.await?;
+	Ok(HttpResponse::Ok().content_type("application/cbor").body(body))
+}
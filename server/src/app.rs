@@ -1,44 +1,258 @@
+use crate::config::{Config, Strategies};
+use crate::error::ApiError;
+use crate::username::UsernameError;
 use actix::prelude::*;
-use rand::{rngs::ThreadRng, Rng};
+use coins_core::api::{Flip, Player};
+use coins_core::{ucb_bound, Dump, Flag, StrategySummary, Summary};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rand_distr::{Bernoulli, Beta, Distribution};
-use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
 
-const EXPLORATION_TRIALS: usize = 30;
+/// Players who flipped within this long count as active
+const ACTIVE_FOR: Duration = Duration::from_secs(60);
 
 /// Actor for managing state
 pub struct AppState {
 	/// a map to the history of flips for each participant
 	past: HashMap<String, Vec<(usize, bool)>>,
+	/// the lowercase usernames handed out so far, kept across rounds
+	names: HashSet<String>,
+	/// the ids of the players who logged in, kept across rounds
+	players: HashSet<String>,
+	/// when each player made their flips in the last second
+	recent: HashMap<String, VecDeque<Instant>>,
+	/// flag a player once they make more flips than this within a second
+	bot_flips_per_second: usize,
+	/// players whose flips looked automated this round
+	flags: Vec<Flag>,
 	/// algorithm states
 	algorithm_state: AlgoState,
+	/// the probability of heads for each coin this round
+	prob_heads: Vec<f64>,
+	/// the rng for the players' flips
+	rng: StdRng,
+	/// where each round's rng for the algorithms and random probabilities come from, so that a
+	/// seed reproduces them however the players flip
+	seeds: StdRng,
+	/// the parameters of the algorithms
+	strategies: Strategies,
+	/// how many flips each player gets, if limited
+	flip_budget: Option<usize>,
+	/// how rounds are run, if they are timed
+	rounds: Option<RoundSettings>,
+	/// the current round, counting from 1
+	round: usize,
+	/// when the current round ends, if rounds are timed
+	round_end: Option<SystemTime>,
+	/// whether the last round has ended
+	finished: bool,
+	/// the directory dumps are written to
+	dumps: PathBuf,
+	/// running totals for /metrics
+	counters: Counters,
+	/// the effective config, echoed into every dump
+	config: String,
 	/// whether or not to print out log messages
 	verbose: bool,
 }
 
+/// Running totals for /metrics, kept across rounds
+struct Counters {
+	/// flips and heads by the players on each coin
+	flips: Vec<(u64, u64)>,
+	/// heads each algorithm got in the rounds that have ended, in the order of `AlgoState::rewards`
+	rewards: [u64; 3],
+	/// how many dumps have been written
+	dumps: u64,
+	/// how long writing them took altogether
+	dump_seconds: f64,
+	/// when each player last flipped
+	last_flip: HashMap<String, Instant>,
+}
+
+impl Counters {
+	fn new(coins: usize) -> Self {
+		Counters {
+			flips: vec![(0, 0); coins],
+			rewards: [0; 3],
+			dumps: 0,
+			dump_seconds: 0.0,
+			last_flip: HashMap::new(),
+		}
+	}
+	fn flip(&mut self, id: &str, coin: usize, result: bool) {
+		self.flips[coin].0 += 1;
+		if result {
+			self.flips[coin].1 += 1;
+		}
+		self.last_flip.insert(id.to_string(), Instant::now());
+	}
+}
+
+/// How timed rounds are run
+struct RoundSettings {
+	/// How long each round lasts
+	length: Duration,
+	/// How many rounds to run. Runs rounds until the server is stopped if not given
+	count: Option<usize>,
+	/// The probabilities of heads for the rounds after the first, in order.
+	/// Rounds past the end of this list get random probabilities
+	scripted: Vec<Vec<f64>>,
+}
+
 impl AppState {
-	pub fn new(rng: ThreadRng, prob_heads: Vec<f64>, verbose: bool) -> AppState {
+	pub fn new(config: &Config) -> AppState {
+		let mut seeds = match config.seed {
+			Some(seed) => StdRng::seed_from_u64(seed),
+			None => StdRng::from_entropy(),
+		};
 		AppState {
 			past: HashMap::new(),
+			names: HashSet::new(),
+			players: HashSet::new(),
+			recent: HashMap::new(),
+			bot_flips_per_second: config.limits.bot_flips_per_second,
+			flags: Vec::new(),
 			algorithm_state: AlgoState::new(
-				rng,
-				prob_heads
-					.iter()
-					.map(|p| Bernoulli::new(*p).unwrap())
-					.collect(),
+				StdRng::seed_from_u64(seeds.gen()),
+				&config.coins,
+				&config.strategies,
 			),
-			verbose,
+			prob_heads: config.coins.clone(),
+			rng: StdRng::seed_from_u64(seeds.gen()),
+			seeds,
+			strategies: config.strategies.clone(),
+			flip_budget: config.flip_budget,
+			rounds: config.rounds.length.map(|seconds| RoundSettings {
+				length: Duration::from_secs(seconds),
+				count: config.rounds.count,
+				scripted: config.rounds.probs.clone(),
+			}),
+			round: 1,
+			round_end: None,
+			finished: false,
+			dumps: PathBuf::from(&config.paths.dumps),
+			counters: Counters::new(config.coins.len()),
+			config: config.echo(),
+			verbose: config.verbose,
 		}
 	}
 	fn to_dump(&self) -> Dump {
 		Dump {
+			coins: self.algorithm_state.arms.len(),
+			round: self.round,
+			coin_probs: self.prob_heads.clone(),
 			algorithms: self.algorithm_state.to_dump(),
 			players: self
 				.past
 				.iter()
 				.map(|e| (e.0.clone(), e.1.clone()))
 				.collect::<Vec<(String, Vec<(usize, bool)>)>>(),
+			flags: self.flags.clone(),
+			config: self.config.clone(),
+		}
+	}
+	/// Write a dump into the dumps directory
+	fn write_dump(&mut self, name: &str) {
+		let start = Instant::now();
+		let path = self.dumps.join(name);
+		let file = std::fs::File::create(&path).expect("Could not open output file");
+		serde_cbor::to_writer(file, &self.to_dump()).expect("Could not write to output file");
+		self.counters.dumps += 1;
+		self.counters.dump_seconds += start.elapsed().as_secs_f64();
+		println!("Dumped to {}", path.display());
+	}
+	/// Only players who logged in to this server may play
+	fn check_player(&self, id: &str) -> Result<(), ApiError> {
+		if self.players.contains(id) {
+			Ok(())
+		} else {
+			Err(ApiError::UnknownPlayer)
+		}
+	}
+	/// Flag a player in the dump, once per reason
+	fn flag(&mut self, id: &str, reason: &str) {
+		if self
+			.flags
+			.iter()
+			.any(|flag| flag.player == id && flag.reason == reason)
+		{
+			return;
+		}
+		if self.verbose {
+			println!("Flagged {}: {}", id, reason);
 		}
+		self.flags.push(Flag {
+			player: id.to_string(),
+			reason: reason.to_string(),
+			flips: self.past.get(id).map_or(0, |past| past.len()),
+		});
+	}
+	/// Flag the player if they have flipped faster than a person clicks
+	fn check_pace(&mut self, id: &str) {
+		let now = Instant::now();
+		let recent = self.recent.entry(id.to_string()).or_default();
+		while recent
+			.front()
+			.is_some_and(|t| now - *t >= Duration::from_secs(1))
+		{
+			recent.pop_front();
+		}
+		recent.push_back(now);
+		if recent.len() > self.bot_flips_per_second {
+			let reason = format!("More than {} flips in a second", self.bot_flips_per_second);
+			self.flag(id, &reason);
+		}
+	}
+	/// Start the timer for the current round
+	fn start_round(&mut self, ctx: &mut Context<Self>) {
+		let length = match &self.rounds {
+			Some(rounds) => rounds.length,
+			None => return,
+		};
+		self.round_end = Some(SystemTime::now() + length);
+		ctx.run_later(length, |act, ctx| act.end_round(ctx));
+		if self.verbose {
+			println!("Round {} started with {:?}", self.round, self.prob_heads);
+		}
+	}
+	/// Save the round that just ended, then start the next one with fresh coins and players
+	fn end_round(&mut self, ctx: &mut Context<Self>) {
+		self.write_dump(&format!("dump_round_{}.cbor", self.round));
+		let rounds = self.rounds.as_ref().expect("Only timed rounds end");
+		if matches!(rounds.count, Some(count) if self.round >= count) {
+			self.round_end = None;
+			self.finished = true;
+			println!("Round {} was the last round", self.round);
+			return;
+		}
+		// round n + 1 uses the (n - 1)th scripted probabilities, round 1 used the command line
+		let coins = self.prob_heads.len();
+		self.prob_heads = match rounds.scripted.get(self.round - 1) {
+			Some(probs) => probs.clone(),
+			None => (0..coins).map(|_| self.seeds.gen()).collect(),
+		};
+		for (total, (_, heads)) in self
+			.counters
+			.rewards
+			.iter_mut()
+			.zip(self.algorithm_state.rewards().iter())
+		{
+			*total += heads;
+		}
+		self.round += 1;
+		self.past.clear();
+		self.recent.clear();
+		self.flags.clear();
+		self.algorithm_state = AlgoState::new(
+			StdRng::seed_from_u64(self.seeds.gen()),
+			&self.prob_heads,
+			&self.strategies,
+		);
+		self.start_round(ctx);
 	}
 }
 
@@ -51,28 +265,45 @@ struct AlgoState {
 	/// The past of Thompson Sampling
 	thompson: ThompsonAlgoState,
 	/// The rng for the algorithms
-	rng: ThreadRng,
+	rng: StdRng,
 	/// The arms / coins
 	arms: Vec<Bernoulli>,
 }
 
 impl AlgoState {
 	/// Create a new AlgoState
-	fn new(rng: ThreadRng, arms: Vec<Bernoulli>) -> AlgoState {
+	fn new(rng: StdRng, prob_heads: &[f64], strategies: &Strategies) -> AlgoState {
+		let arms: Vec<Bernoulli> = prob_heads
+			.iter()
+			.map(|p| Bernoulli::new(*p).unwrap())
+			.collect();
 		AlgoState {
-			naive: NaiveAlgoState::new(arms.len()),
-			ucb: UcbAlgoState::new(arms.len()),
-			thompson: ThompsonAlgoState::new(arms.len()),
+			naive: NaiveAlgoState::new(arms.len(), strategies),
+			ucb: UcbAlgoState::new(arms.len(), strategies),
+			thompson: ThompsonAlgoState::new(arms.len(), strategies),
 			rng,
 			arms,
 		}
 	}
+	/// How many times each algorithm has flipped
+	fn steps(&self) -> usize {
+		self.thompson.past_flips.len()
+	}
 	/// Run every algorithm once
 	fn update(&mut self) {
 		self.naive.choose_flip(&mut self.rng, &self.arms);
 		self.ucb.choose_flip(&mut self.rng, &self.arms);
 		self.thompson.choose_flip(&mut self.rng, &self.arms);
 	}
+	/// How many heads each algorithm has flipped this round
+	fn rewards(&self) -> [(&'static str, u64); 3] {
+		let heads = |past: &[(usize, bool)]| past.iter().filter(|(_, result)| *result).count() as u64;
+		[
+			("naive", heads(&self.naive.past_flips)),
+			("ucb", heads(&self.ucb.past_flips)),
+			("thompson", heads(&self.thompson.past_flips)),
+		]
+	}
 	/// Dump the state of the algorithms
 	fn to_dump(&self) -> Vec<(String, Vec<(usize, bool)>)> {
 		vec![
@@ -84,9 +315,9 @@ impl AlgoState {
 }
 
 trait Algorithm {
-	fn new(num_arms: usize) -> Self;
-	fn choose_flip(&mut self, rng: &mut ThreadRng, arms: &[Bernoulli]);
-	fn flip(&mut self, rng: &mut ThreadRng, probs: &[Bernoulli], arm: usize);
+	fn new(num_arms: usize, strategies: &Strategies) -> Self;
+	fn choose_flip(&mut self, rng: &mut StdRng, arms: &[Bernoulli]);
+	fn flip(&mut self, rng: &mut StdRng, probs: &[Bernoulli], arm: usize);
 	fn to_dump(&self) -> (String, Vec<(usize, bool)>);
 }
 
@@ -98,18 +329,21 @@ struct NaiveAlgoState {
 	/// Best coin.
 	/// The best coin seen so far, evaluated once after the exploration phase has ended
 	best_coin: Option<usize>,
+	/// How many flips the exploration phase lasts
+	exploration: usize,
 }
 
 impl Algorithm for NaiveAlgoState {
-	fn new(num_arms: usize) -> NaiveAlgoState {
+	fn new(num_arms: usize, strategies: &Strategies) -> NaiveAlgoState {
 		NaiveAlgoState {
 			stats: vec![(0, 0); num_arms],
 			past_flips: Vec::new(),
 			best_coin: None,
+			exploration: strategies.naive.exploration,
 		}
 	}
-	fn choose_flip(&mut self, rng: &mut ThreadRng, probs: &[Bernoulli]) {
-		if self.past_flips.len() < EXPLORATION_TRIALS {
+	fn choose_flip(&mut self, rng: &mut StdRng, probs: &[Bernoulli]) {
+		if self.past_flips.len() < self.exploration {
 			// continue exploration phase
 			let arm = rng.gen_range(0..probs.len());
 			self.flip(rng, probs, arm);
@@ -136,7 +370,7 @@ impl Algorithm for NaiveAlgoState {
 			self.flip(rng, probs, index);
 		}
 	}
-	fn flip(&mut self, rng: &mut ThreadRng, probs: &[Bernoulli], arm: usize) {
+	fn flip(&mut self, rng: &mut StdRng, probs: &[Bernoulli], arm: usize) {
 		let result = probs[arm].sample(rng);
 		if result {
 			self.stats[arm].0 += 1;
@@ -148,10 +382,7 @@ impl Algorithm for NaiveAlgoState {
 	fn to_dump(&self) -> (String, Vec<(usize, bool)>) {
 		(
 			"Naive Strategy".to_string(),
-			self.past_flips
-				.iter()
-				.copied()
-				.collect::<Vec<(usize, bool)>>(),
+			self.past_flips.to_vec(),
 		)
 	}
 }
@@ -163,29 +394,32 @@ struct UcbAlgoState {
 	arm_results: Vec<(u32, u32)>,
 	/// Total flips so far
 	total_flips: u32,
+	/// How wide the confidence bound is
+	confidence: f64,
 }
 
 impl Algorithm for UcbAlgoState {
-	fn new(num_arms: usize) -> UcbAlgoState {
+	fn new(num_arms: usize, strategies: &Strategies) -> UcbAlgoState {
 		UcbAlgoState {
 			past_flips: Vec::new(),
 			arm_results: vec![(0, 0); num_arms],
 			total_flips: 0,
+			confidence: strategies.ucb.confidence,
 		}
 	}
-	fn choose_flip(&mut self, rng: &mut ThreadRng, arms: &[Bernoulli]) {
+	fn choose_flip(&mut self, rng: &mut StdRng, arms: &[Bernoulli]) {
 		let arm = self
 			.arm_results
 			.iter()
 			.enumerate()
 			.fold((0, 0.0), |a, index_heads_tails| {
-				let proportion = index_heads_tails.1 .0 as f64
-					/ (index_heads_tails.1 .0 as f64 + index_heads_tails.1 .1 as f64);
-				let confidence = proportion
-					+ f64::sqrt(
-						2.0 * f64::log(self.total_flips as f64, 10.0)
-							/ (index_heads_tails.1 .0 + index_heads_tails.1 .1) as f64,
-					);
+				let (heads, tails) = *index_heads_tails.1;
+				let confidence = ucb_bound(
+					heads as usize,
+					tails as usize,
+					self.total_flips as usize,
+					self.confidence,
+				);
 				if confidence > a.1 || confidence.is_nan() {
 					(index_heads_tails.0, confidence)
 				} else {
@@ -194,7 +428,7 @@ impl Algorithm for UcbAlgoState {
 			});
 		self.flip(rng, arms, arm.0);
 	}
-	fn flip(&mut self, rng: &mut ThreadRng, arms: &[Bernoulli], arm: usize) {
+	fn flip(&mut self, rng: &mut StdRng, arms: &[Bernoulli], arm: usize) {
 		let result = arms[arm].sample(rng);
 		if result {
 			self.arm_results[arm].0 += 1;
@@ -207,10 +441,7 @@ impl Algorithm for UcbAlgoState {
 	fn to_dump(&self) -> (String, Vec<(usize, bool)>) {
 		(
 			"UCB Strategy".to_string(),
-			self.past_flips
-				.iter()
-				.copied()
-				.collect::<Vec<(usize, bool)>>(),
+			self.past_flips.to_vec(),
 		)
 	}
 }
@@ -218,18 +449,20 @@ impl Algorithm for UcbAlgoState {
 struct ThompsonAlgoState {
 	/// The past of Thompson
 	past_flips: Vec<(usize, bool)>,
-	/// Heads, tails, and beta distribution (storing = less update) seen for each arm
-	arm_results: Vec<(u32, u32, Beta<f64>)>,
+	/// Heads, tails (starting from the prior), and beta distribution (storing = less update) seen
+	/// for each arm
+	arm_results: Vec<(f64, f64, Beta<f64>)>,
 }
 
 impl Algorithm for ThompsonAlgoState {
-	fn new(num_arms: usize) -> ThompsonAlgoState {
+	fn new(num_arms: usize, strategies: &Strategies) -> ThompsonAlgoState {
+		let [heads, tails] = strategies.thompson.prior;
 		ThompsonAlgoState {
 			past_flips: Vec::new(),
-			arm_results: vec![(1, 1, Beta::new(1.0, 1.0).unwrap()); num_arms],
+			arm_results: vec![(heads, tails, Beta::new(heads, tails).unwrap()); num_arms],
 		}
 	}
-	fn choose_flip(&mut self, rng: &mut ThreadRng, arms: &[Bernoulli]) {
+	fn choose_flip(&mut self, rng: &mut StdRng, arms: &[Bernoulli]) {
 		// choose the arm with the highest sample from its beta distribution
 		let arm = self
 			.arm_results
@@ -246,70 +479,238 @@ impl Algorithm for ThompsonAlgoState {
 			.0;
 		self.flip(rng, arms, arm);
 	}
-	fn flip(&mut self, rng: &mut ThreadRng, arms: &[Bernoulli], index: usize) {
-		let mut arm = &mut self.arm_results[index];
+	fn flip(&mut self, rng: &mut StdRng, arms: &[Bernoulli], index: usize) {
+		let arm = &mut self.arm_results[index];
 		let result = arms[index].sample(rng);
 		if result {
-			arm.0 += 1;
+			arm.0 += 1.0;
 		} else {
-			arm.1 += 1;
+			arm.1 += 1.0;
 		}
-		arm.2 = Beta::new(arm.0 as f64, arm.1 as f64).unwrap();
+		arm.2 = Beta::new(arm.0, arm.1).unwrap();
 		self.past_flips.push((index, result));
 	}
 	fn to_dump(&self) -> (String, Vec<(usize, bool)>) {
 		(
 			"Thompson Strategy".to_string(),
-			self.past_flips
-				.iter()
-				.copied()
-				.collect::<Vec<(usize, bool)>>(),
+			self.past_flips.to_vec(),
 		)
 	}
 }
 
 impl Actor for AppState {
 	type Context = actix::Context<Self>;
+	fn started(&mut self, ctx: &mut Self::Context) {
+		self.start_round(ctx);
+	}
 }
 
-#[derive(Serialize)]
-pub struct Dump {
-	algorithms: Vec<(String, Vec<(usize, bool)>)>,
-	players: Vec<(String, Vec<(usize, bool)>)>,
-}
-
+/// Flip a coin for a player and record the result \
+/// Refused for unknown players and coins, when the player has no flips left, or when the last
+/// round has ended
 #[derive(Message, Debug)]
-#[rtype(result = "()")]
-pub struct CoinFlipped {
+#[rtype(result = "Result<Flip, ApiError>")]
+pub struct FlipCoin {
 	pub user_id: String,
 	pub arm: usize,
-	pub result: bool,
 }
 
 #[derive(Message, Debug)]
 #[rtype(result = "()")]
 pub struct Flush {}
 
-/// Register a new player \
+/// The game so far, as it would be flushed
+#[derive(Message, Debug)]
+#[rtype(result = "Dump")]
+pub struct GetDump {}
+
+/// What /metrics reports from the actor
+#[derive(Message, Debug)]
+#[rtype(result = "Metrics")]
+pub struct GetMetrics {}
+
+pub struct Metrics {
+	/// The current round, counting from 1
+	pub round: usize,
+	/// Everyone who logged in
+	pub players: usize,
+	/// Players who flipped within the last minute
+	pub active_players: usize,
+	/// Flips and heads by the players on each coin, over every round
+	pub flips: Vec<(u64, u64)>,
+	/// Heads each algorithm got over every round
+	pub rewards: Vec<(&'static str, u64)>,
+	/// How many dumps have been written
+	pub dumps: u64,
+	/// How long writing them took altogether
+	pub dump_seconds: f64,
+}
+
+/// A player's score and flips this round \
 /// Forwarded from App
 #[derive(Message, Debug)]
-#[rtype(i32)]
-pub struct GetCount {
+#[rtype(result = "Result<Player, ApiError>")]
+pub struct GetPlayer {
 	pub id: String,
 }
 
-/// Handler for CoinFlipped message.
-impl Handler<CoinFlipped> for AppState {
+/// How a player did against the algorithms \
+/// Forwarded from App
+#[derive(Message, Debug)]
+#[rtype(result = "Result<Summary, ApiError>")]
+pub struct GetSummary {
+	pub id: String,
+}
+
+/// Log a player in under a username, responding with their id \
+/// A player who logs in again under their own name keeps their id, and with it their flips.
+/// Refused if someone else already has the name
+#[derive(Message, Debug)]
+#[rtype(result = "Result<String, ApiError>")]
+pub struct LogIn {
+	pub name: String,
+	/// The id from the player's current session, if any
+	pub previous: Option<String>,
+}
+
+/// Flag a player in the dump as probably not a person \
+/// Sent by the rate limiter
+#[derive(Message, Debug)]
+#[rtype(result = "()")]
+pub struct FlagPlayer {
+	pub id: String,
+	pub reason: String,
+}
+
+/// The current round and when it ends \
+/// Forwarded from App
+#[derive(Message, Debug)]
+#[rtype(result = "RoundInfo")]
+pub struct GetRound {}
+
+pub struct RoundInfo {
+	/// The current round, counting from 1
+	pub number: usize,
+	/// When the round ends, if rounds are timed
+	pub end: Option<SystemTime>,
+}
+
+impl RoundInfo {
+	/// The end of the round in milliseconds since the unix epoch
+	pub fn end_millis(&self) -> Option<u64> {
+		self.end
+			.and_then(|end| end.duration_since(UNIX_EPOCH).ok())
+			.map(|end| end.as_millis() as u64)
+	}
+}
+
+/// Handler for FlipCoin message.
+impl Handler<FlipCoin> for AppState {
+	type Result = Result<Flip, ApiError>;
+	fn handle(&mut self, msg: FlipCoin, _: &mut Context<Self>) -> Self::Result {
+		if self.verbose {
+			println!("{:?}", msg);
+		}
+		self.check_player(&msg.user_id)?;
+		if self.finished {
+			return Err(ApiError::RoundOver);
+		}
+		let arm = self
+			.algorithm_state
+			.arms
+			.get(msg.arm)
+			.ok_or_else(|| ApiError::InvalidCoin(msg.arm.to_string()))?;
+		let past = self.past.entry(msg.user_id.clone()).or_default();
+		if flips_left(self.flip_budget, past) == Some(0) {
+			return Err(ApiError::NoFlipsLeft);
+		}
+		let result = arm.sample(&mut self.rng);
+		past.push((msg.arm, result));
+		self.counters.flip(&msg.user_id, msg.arm, result);
+		let flip = Flip {
+			result,
+			score: score(past),
+			index: past.len() - 1,
+			flips_left: flips_left(self.flip_budget, past),
+		};
+		// keep the algorithms level with the player furthest along, rather than one step per
+		// flip, so that every player is compared against the algorithms over the same horizon
+		let horizon = past.len();
+		while self.algorithm_state.steps() < horizon {
+			self.algorithm_state.update();
+		}
+		self.check_pace(&msg.user_id);
+		Ok(flip)
+	}
+}
+
+/// Handler for LogIn message.
+impl Handler<LogIn> for AppState {
+	type Result = Result<String, ApiError>;
+	fn handle(&mut self, msg: LogIn, _: &mut Context<Self>) -> Self::Result {
+		if self.verbose {
+			println!("{:?}", msg);
+		}
+		let name = msg.name.to_lowercase();
+		if let Some(previous) = msg.previous {
+			let own_name = previous
+				.split_once('_')
+				.is_some_and(|(_, own)| own.to_lowercase() == name);
+			if own_name && self.players.contains(&previous) {
+				return Ok(previous);
+			}
+		}
+		if !self.names.insert(name) {
+			return Err(UsernameError::Taken.into());
+		}
+		let id = Uuid::new_v4().to_string() + "_" + &msg.name;
+		self.players.insert(id.clone());
+		Ok(id)
+	}
+}
+
+/// Handler for FlagPlayer message.
+impl Handler<FlagPlayer> for AppState {
 	type Result = ();
-	fn handle(&mut self, msg: CoinFlipped, _: &mut Context<Self>) -> Self::Result {
+	fn handle(&mut self, msg: FlagPlayer, _: &mut Context<Self>) -> Self::Result {
+		self.flag(&msg.id, &msg.reason);
+	}
+}
+
+/// Handler for GetRound message.
+impl Handler<GetRound> for AppState {
+	type Result = MessageResult<GetRound>;
+	fn handle(&mut self, _: GetRound, _: &mut Context<Self>) -> Self::Result {
+		MessageResult(RoundInfo {
+			number: self.round,
+			end: self.round_end,
+		})
+	}
+}
+
+/// Handler for GetSummary message.
+impl Handler<GetSummary> for AppState {
+	type Result = Result<Summary, ApiError>;
+	fn handle(&mut self, msg: GetSummary, _: &mut Context<Self>) -> Self::Result {
 		if self.verbose {
 			println!("{:?}", msg);
 		}
-		self.past
-			.entry(msg.user_id)
-			.or_insert_with(Vec::new)
-			.push((msg.arm, msg.result));
-		self.algorithm_state.update();
+		self.check_player(&msg.id)?;
+		let past = self.past.get(&msg.id).map_or(&[][..], |past| &past[..]);
+		let heads = |past: &[(usize, bool)]| past.iter().filter(|(_, result)| *result).count();
+		Ok(Summary {
+			flips: past.len(),
+			heads: heads(past),
+			strategies: self
+				.algorithm_state
+				.to_dump()
+				.into_iter()
+				.map(|(name, algorithm_past)| StrategySummary {
+					name,
+					heads: heads(&algorithm_past[..past.len().min(algorithm_past.len())]),
+				})
+				.collect(),
+		})
 	}
 }
 
@@ -320,22 +721,75 @@ impl Handler<Flush> for AppState {
 		if self.verbose {
 			println!("{:?}", msg);
 		}
-		let file = std::fs::File::create("dump.cbor").expect("Could not open output file");
-		serde_cbor::to_writer(file, &self.to_dump()).expect("Could not write to output file");
-		println!("Dumped to dump.cbor");
+		self.write_dump("dump.cbor");
 	}
 }
 
-/// Handler for CoinFlipped message.
-impl Handler<GetCount> for AppState {
-	type Result = i32;
-	fn handle(&mut self, msg: GetCount, _: &mut Context<Self>) -> Self::Result {
+/// Handler for GetDump message.
+impl Handler<GetDump> for AppState {
+	type Result = MessageResult<GetDump>;
+	fn handle(&mut self, msg: GetDump, _: &mut Context<Self>) -> Self::Result {
 		if self.verbose {
 			println!("{:?}", msg);
 		}
-		self.past
-			.get(&msg.id)
-			.map(|past| past.iter().fold(0, |a, e| if e.1 { a + 1 } else { a - 1 }))
-			.unwrap_or(0)
+		MessageResult(self.to_dump())
 	}
 }
+
+/// Handler for GetMetrics message.
+impl Handler<GetMetrics> for AppState {
+	type Result = MessageResult<GetMetrics>;
+	fn handle(&mut self, _: GetMetrics, _: &mut Context<Self>) -> Self::Result {
+		self.counters
+			.last_flip
+			.retain(|_, last| last.elapsed() < ACTIVE_FOR);
+		MessageResult(Metrics {
+			round: self.round,
+			players: self.players.len(),
+			active_players: self.counters.last_flip.len(),
+			flips: self.counters.flips.clone(),
+			rewards: self
+				.algorithm_state
+				.rewards()
+				.iter()
+				.zip(self.counters.rewards.iter())
+				.map(|((name, heads), past)| (*name, heads + past))
+				.collect(),
+			dumps: self.counters.dumps,
+			dump_seconds: self.counters.dump_seconds,
+		})
+	}
+}
+
+/// Handler for GetPlayer message.
+impl Handler<GetPlayer> for AppState {
+	type Result = Result<Player, ApiError>;
+	fn handle(&mut self, msg: GetPlayer, _: &mut Context<Self>) -> Self::Result {
+		if self.verbose {
+			println!("{:?}", msg);
+		}
+		self.check_player(&msg.id)?;
+		let past = self.past.get(&msg.id).map_or(&[][..], |past| &past[..]);
+		Ok(Player {
+			name: msg
+				.id
+				.split_once('_')
+				.map_or(msg.id.as_str(), |(_, name)| name)
+				.to_string(),
+			score: score(past),
+			flips: past.len(),
+			flips_left: flips_left(self.flip_budget, past),
+			id: msg.id,
+		})
+	}
+}
+
+/// Heads minus tails
+fn score(past: &[(usize, bool)]) -> i32 {
+	past.iter().fold(0, |a, e| if e.1 { a + 1 } else { a - 1 })
+}
+
+/// How many flips a player with the given history has left, if limited
+fn flips_left(budget: Option<usize>, past: &[(usize, bool)]) -> Option<usize> {
+	budget.map(|budget| budget.saturating_sub(past.len()))
+}
@@ -0,0 +1,103 @@
+use actix_files::NamedFile;
+use actix_web::{error, HttpRequest, HttpResponse, Result};
+use std::path::{Component, Path, PathBuf};
+
+/// Where the login, game and view pages are served from
+pub enum Assets {
+	/// Read from a directory holding `login/`, `game/` and `view/` on every request
+	Disk(PathBuf),
+	/// Built into the server with the `embed` feature
+	#[cfg(feature = "embed")]
+	Embedded,
+}
+
+impl Assets {
+	/// The given directory, otherwise the embedded pages if there are any, otherwise the
+	/// repository the server was built from
+	pub fn new(root: Option<&str>) -> Self {
+		match root {
+			Some(root) => Assets::Disk(PathBuf::from(root)),
+			#[cfg(feature = "embed")]
+			None => Assets::Embedded,
+			#[cfg(not(feature = "embed"))]
+			None => Assets::Disk(PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/.."))),
+		}
+	}
+	/// Serve a file, given by its path under the asset root. Missing files and paths that could
+	/// climb out of the root are not found
+	pub fn serve(&self, path: &str, req: &HttpRequest) -> Result<HttpResponse> {
+		if !Path::new(path)
+			.components()
+			.all(|c| matches!(c, Component::Normal(_)))
+		{
+			return Err(error::ErrorNotFound("Not found"));
+		}
+		match self {
+			Assets::Disk(root) => NamedFile::open(root.join(path))
+				.map_err(|_| error::ErrorNotFound("Not found"))?
+				.into_response(req),
+			#[cfg(feature = "embed")]
+			Assets::Embedded => embedded::serve(path, req),
+		}
+	}
+}
+
+#[cfg(feature = "embed")]
+mod embedded {
+	use super::*;
+	use actix_web::http;
+	use rust_embed::RustEmbed;
+
+	/// Only what the pages load is embedded. Build the pages with wasm-pack before the server
+	#[derive(RustEmbed)]
+	#[folder = ".."]
+	#[include = "login/index.html"]
+	#[include = "login/pkg/*"]
+	#[include = "login/style/*"]
+	#[include = "game/index.html"]
+	#[include = "game/pkg/*"]
+	#[include = "game/style/*"]
+	#[include = "game/audio/*"]
+	#[include = "view/index.html"]
+	#[include = "view/pkg/*"]
+	#[include = "view/style/*"]
+	#[exclude = "*/pkg/.gitignore"]
+	struct Files;
+
+	/// Embedded files can only change with the binary, so browsers revalidate against a hash of
+	/// the contents and get `304` while it matches
+	pub fn serve(path: &str, req: &HttpRequest) -> Result<HttpResponse> {
+		let file = Files::get(path).ok_or_else(|| error::ErrorNotFound("Not found"))?;
+		let etag = format!(
+			"\"{}\"",
+			file.metadata
+				.sha256_hash()
+				.iter()
+				.map(|byte| format!("{:02x}", byte))
+				.collect::<String>()
+		);
+		let fresh = req
+			.headers()
+			.get(http::header::IF_NONE_MATCH)
+			.and_then(|value| value.to_str().ok())
+			.is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag));
+		let mut response = if fresh {
+			HttpResponse::NotModified()
+		} else {
+			HttpResponse::Ok()
+		};
+		response
+			.header(http::header::ETAG, etag)
+			.header(http::header::CACHE_CONTROL, "no-cache");
+		if fresh {
+			return Ok(response.finish());
+		}
+		Ok(response
+			.content_type(
+				mime_guess::from_path(path)
+					.first_or_octet_stream()
+					.to_string(),
+			)
+			.body(file.data.into_owned()))
+	}
+}
@@ -1,12 +1,81 @@
 use clap::{Clap, AppSettings};
 
-/// Running a server with arguments for the coins
+/// Running a server with arguments for the coins. Flags override the config file
 #[derive(Clap, Clone)]
 #[clap(setting = AppSettings::ColoredHelp)]
 pub struct Opts {
-    /// A list of probabilities for each coin
+    /// A list of probabilities for each coin, at least 3
     pub coin_probs: Vec<f64>,
+	/// A TOML or YAML file to read everything else from. See example.toml
+	#[clap(short, long)]
+	pub config: Option<String>,
 	/// Verbose output
 	#[clap(short, long)]
 	pub verbose: bool,
-}
\ No newline at end of file
+	/// The name of the room, shown on the game page. "coins" if not given
+	#[clap(long)]
+	pub room: Option<String>,
+	/// How many flips each player gets. Unlimited if not given
+	#[clap(long)]
+	pub flip_budget: Option<usize>,
+	/// Comma separated labels for the coins, in order. Unlabelled coins are numbered
+	#[clap(long, use_delimiter = true)]
+	pub labels: Vec<String>,
+	/// Seeds the coins and the algorithms, so a game can be run again with the same luck.
+	/// At most 9223372036854775807. A random seed is used if not given
+	#[clap(long)]
+	pub seed: Option<u64>,
+	/// How long each round lasts, in seconds. Rounds are not timed if not given
+	#[clap(long)]
+	pub round_length: Option<u64>,
+	/// How many timed rounds to run. Runs rounds until stopped if not given
+	#[clap(long)]
+	pub rounds: Option<usize>,
+	/// Comma separated probabilities for the round after the first, repeat for later rounds.
+	/// Rounds without probabilities get random ones
+	#[clap(long, number_of_values = 1)]
+	pub round_probs: Vec<String>,
+	/// The secret used to sign session cookies, at least 32 characters long.
+	/// A random one is used if not given, which signs every player out when the server restarts
+	#[clap(long)]
+	pub secret: Option<String>,
+	/// A file of words, one per line, that may not appear in usernames
+	#[clap(long)]
+	pub blocklist: Option<String>,
+	/// How many flips a session may make per minute, 240 if not given. 0 for no limit
+	#[clap(long)]
+	pub session_flips_per_minute: Option<usize>,
+	/// How many flips may come from one IP address per minute, 6000 if not given. 0 for no limit.
+	/// Players behind the same router share an address
+	#[clap(long)]
+	pub ip_flips_per_minute: Option<usize>,
+	/// Flag a player in the dump once they make more than this many flips within a second.
+	/// 10 if not given
+	#[clap(long)]
+	pub bot_flips_per_second: Option<usize>,
+	/// The address to listen on. 0.0.0.0 if not given
+	#[clap(long)]
+	pub host: Option<String>,
+	/// The port to listen on. 8080 if not given
+	#[clap(long)]
+	pub port: Option<u16>,
+	/// A PEM certificate chain to serve HTTPS with. Needs --tls-key
+	#[clap(long, requires = "tls-key")]
+	pub tls_cert: Option<String>,
+	/// The PEM private key (PKCS #8 or RSA) for --tls-cert
+	#[clap(long, requires = "tls-cert")]
+	pub tls_key: Option<String>,
+	/// The directory holding the login/, game/ and view/ pages. Defaults to the pages built into the
+	/// server with the embed feature, or else the repository the server was built from
+	#[clap(long)]
+	pub assets: Option<String>,
+	/// The directory dumps are written to. The current directory if not given
+	#[clap(long)]
+	pub dumps: Option<String>,
+	/// The username for flushing and the view page. Anyone may use them if no admin is given
+	#[clap(long)]
+	pub admin_user: Option<String>,
+	/// The password for --admin-user
+	#[clap(long)]
+	pub admin_password: Option<String>,
+}
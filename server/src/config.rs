@@ -0,0 +1,345 @@
+use crate::cli::Opts;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Shown in dumps instead of the secret and the admin password
+const HIDDEN: &str = "<hidden>";
+
+/// Everything the server is run with. Read from the TOML or YAML file given with `--config`, then
+/// overridden by any flags given. Anything left out takes its default
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+	/// The name of the room, shown on the game page
+	pub room: String,
+	/// The probability of heads for each coin
+	pub coins: Vec<f64>,
+	/// Labels for the coins, in order. Unlabelled coins are numbered
+	pub labels: Vec<String>,
+	/// How many flips each player gets. Unlimited if not given
+	pub flip_budget: Option<usize>,
+	/// Seeds the coins and the algorithms, so a game can be run again with the same luck.
+	/// At most 9223372036854775807. A random seed is used if not given
+	pub seed: Option<u64>,
+	/// Verbose output
+	pub verbose: bool,
+	pub rounds: Rounds,
+	pub strategies: Strategies,
+	pub limits: Limits,
+	pub server: Server,
+	pub paths: Paths,
+	/// Who may flush and watch the replay. Anyone may if not given
+	pub admin: Option<Admin>,
+}
+
+/// How timed rounds are run
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rounds {
+	/// How long each round lasts, in seconds. Rounds are not timed if not given
+	pub length: Option<u64>,
+	/// How many rounds to run. Runs rounds until stopped if not given
+	pub count: Option<usize>,
+	/// The probabilities for the rounds after the first, in order.
+	/// Rounds without probabilities get random ones
+	pub probs: Vec<Vec<f64>>,
+}
+
+/// The parameters of the algorithms the players are up against
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Strategies {
+	pub naive: Naive,
+	pub ucb: Ucb,
+	pub thompson: Thompson,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Naive {
+	/// How many coins to flip at random before settling on the best one
+	pub exploration: usize,
+}
+
+impl Default for Naive {
+	fn default() -> Self {
+		Naive {
+			exploration: coins_core::dump::DEFAULT_EXPLORATION,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ucb {
+	/// How wide the confidence bound is. Higher explores more
+	pub confidence: f64,
+}
+
+impl Default for Ucb {
+	fn default() -> Self {
+		Ucb {
+			confidence: coins_core::dump::DEFAULT_CONFIDENCE,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thompson {
+	/// The heads and tails each coin's beta distribution starts from
+	pub prior: [f64; 2],
+}
+
+impl Default for Thompson {
+	fn default() -> Self {
+		Thompson {
+			prior: coins_core::dump::DEFAULT_PRIOR,
+		}
+	}
+}
+
+/// How fast players may flip
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+	/// How many flips a session may make per minute. 0 for no limit
+	pub session_flips_per_minute: usize,
+	/// How many flips may come from one IP address per minute. 0 for no limit
+	pub ip_flips_per_minute: usize,
+	/// Flag a player in the dump once they make more than this many flips within a second
+	pub bot_flips_per_second: usize,
+}
+
+impl Default for Limits {
+	fn default() -> Self {
+		Limits {
+			session_flips_per_minute: 240,
+			ip_flips_per_minute: 6000,
+			bot_flips_per_second: 10,
+		}
+	}
+}
+
+/// Where and how the server listens
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Server {
+	/// The address to listen on
+	pub host: String,
+	/// The port to listen on
+	pub port: u16,
+	/// A PEM certificate chain to serve HTTPS with, along with `tls_key`
+	pub tls_cert: Option<String>,
+	/// The PEM private key (PKCS #8 or RSA) for `tls_cert`
+	pub tls_key: Option<String>,
+	/// The secret used to sign session cookies, at least 32 characters long.
+	/// A random one is used if not given
+	pub secret: Option<String>,
+}
+
+impl Default for Server {
+	fn default() -> Self {
+		Server {
+			host: "0.0.0.0".to_string(),
+			port: 8080,
+			tls_cert: None,
+			tls_key: None,
+			secret: None,
+		}
+	}
+}
+
+/// Files the server reads and writes
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Paths {
+	/// The directory holding the login/, game/ and view/ pages. Defaults to the pages built into
+	/// the server with the embed feature, or else the repository the server was built from
+	pub assets: Option<String>,
+	/// A file of words, one per line, that may not appear in usernames
+	pub blocklist: Option<String>,
+	/// The directory dumps are written to
+	pub dumps: String,
+}
+
+impl Default for Paths {
+	fn default() -> Self {
+		Paths {
+			assets: None,
+			blocklist: None,
+			dumps: ".".to_string(),
+		}
+	}
+}
+
+/// The credentials for flushing and for the view page, checked with HTTP basic authentication
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Admin {
+	pub username: String,
+	pub password: String,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Config {
+			room: "coins".to_string(),
+			coins: Vec::new(),
+			labels: Vec::new(),
+			flip_budget: None,
+			seed: None,
+			verbose: false,
+			rounds: Rounds::default(),
+			strategies: Strategies::default(),
+			limits: Limits::default(),
+			server: Server::default(),
+			paths: Paths::default(),
+			admin: None,
+		}
+	}
+}
+
+impl Config {
+	/// Read the file given with `--config`, if any, and override it with the flags given
+	pub fn load(opts: &Opts) -> Result<Config, String> {
+		let mut config = match &opts.config {
+			Some(path) => Config::read(path)?,
+			None => Config::default(),
+		};
+		config.apply(opts)?;
+		config.check()?;
+		Ok(config)
+	}
+	/// Files ending in `.yaml` or `.yml` are YAML, anything else is TOML
+	fn read(path: &str) -> Result<Config, String> {
+		let contents = std::fs::read_to_string(path)
+			.map_err(|e| format!("Could not read the config {}: {}", path, e))?;
+		match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+			Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)
+				.map_err(|e| format!("Could not read the config {}: {}", path, e)),
+			_ => toml::from_str(&contents)
+				.map_err(|e| format!("Could not read the config {}: {}", path, e)),
+		}
+	}
+	/// Flags win over the file
+	fn apply(&mut self, opts: &Opts) -> Result<(), String> {
+		if !opts.coin_probs.is_empty() {
+			self.coins = opts.coin_probs.clone();
+		}
+		if !opts.labels.is_empty() {
+			self.labels = opts.labels.clone();
+		}
+		if !opts.round_probs.is_empty() {
+			self.rounds.probs = opts
+				.round_probs
+				.iter()
+				.map(|probs| {
+					probs
+						.split(',')
+						.map(|p| p.trim().parse::<f64>())
+						.collect::<Result<Vec<f64>, _>>()
+						.map_err(|_| format!("Could not read round probabilities \"{}\"", probs))
+				})
+				.collect::<Result<_, _>>()?;
+		}
+		self.verbose |= opts.verbose;
+		override_with(&mut self.room, &opts.room);
+		override_some(&mut self.flip_budget, &opts.flip_budget);
+		override_some(&mut self.seed, &opts.seed);
+		override_some(&mut self.rounds.length, &opts.round_length);
+		override_some(&mut self.rounds.count, &opts.rounds);
+		override_with(&mut self.limits.session_flips_per_minute, &opts.session_flips_per_minute);
+		override_with(&mut self.limits.ip_flips_per_minute, &opts.ip_flips_per_minute);
+		override_with(&mut self.limits.bot_flips_per_second, &opts.bot_flips_per_second);
+		override_with(&mut self.server.host, &opts.host);
+		override_with(&mut self.server.port, &opts.port);
+		override_some(&mut self.server.tls_cert, &opts.tls_cert);
+		override_some(&mut self.server.tls_key, &opts.tls_key);
+		override_some(&mut self.server.secret, &opts.secret);
+		override_some(&mut self.paths.assets, &opts.assets);
+		override_some(&mut self.paths.blocklist, &opts.blocklist);
+		override_with(&mut self.paths.dumps, &opts.dumps);
+		if opts.admin_user.is_some() || opts.admin_password.is_some() {
+			let admin = self.admin.get_or_insert_with(Admin::default);
+			override_with(&mut admin.username, &opts.admin_user);
+			override_with(&mut admin.password, &opts.admin_password);
+		}
+		Ok(())
+	}
+	/// Refuse anything the server cannot run with
+	fn check(&self) -> Result<(), String> {
+		if self.coins.len() < 3 {
+			return Err("At least 3 coin probabilities are required".to_string());
+		}
+		if self.seed.is_some_and(|seed| seed > i64::MAX as u64) {
+			// TOML integers are signed, so a larger seed could not be echoed into the dumps
+			return Err(format!("The seed must be at most {}", i64::MAX));
+		}
+		if self.labels.len() > self.coins.len() {
+			return Err("There are more labels than coins".to_string());
+		}
+		if self.rounds.probs.iter().any(|probs| probs.len() != self.coins.len()) {
+			return Err(format!(
+				"Every round needs a probability for each of the {} coins",
+				self.coins.len()
+			));
+		}
+		if self
+			.coins
+			.iter()
+			.chain(self.rounds.probs.iter().flatten())
+			.any(|p| !(0.0..=1.0).contains(p))
+		{
+			return Err("Probabilities must be between 0 and 1".to_string());
+		}
+		if !(self.strategies.ucb.confidence >= 0.0 && self.strategies.ucb.confidence.is_finite()) {
+			return Err("The UCB confidence must be a number of at least 0".to_string());
+		}
+		if !self
+			.strategies
+			.thompson
+			.prior
+			.iter()
+			.all(|p| *p > 0.0 && p.is_finite())
+		{
+			return Err("The Thompson prior must be two numbers above 0".to_string());
+		}
+		if self.server.tls_cert.is_some() != self.server.tls_key.is_some() {
+			return Err("HTTPS needs both a certificate and a key".to_string());
+		}
+		if matches!(&self.server.secret, Some(secret) if secret.len() < 32) {
+			return Err("The secret must be at least 32 characters long".to_string());
+		}
+		if matches!(&self.admin, Some(admin) if admin.username.is_empty() || admin.password.is_empty())
+		{
+			return Err("The admin needs both a username and a password".to_string());
+		}
+		Ok(())
+	}
+	/// The config as TOML with the secret and the admin password hidden, for the dumps
+	pub fn echo(&self) -> String {
+		let mut config = self.clone();
+		if let Some(secret) = &mut config.server.secret {
+			*secret = HIDDEN.to_string();
+		}
+		if let Some(admin) = &mut config.admin {
+			admin.password = HIDDEN.to_string();
+		}
+		toml::to_string(&config).expect("Could not write the config as TOML")
+	}
+}
+
+/// Replace the value with the flag, if it was given
+fn override_with<T: Clone>(value: &mut T, flag: &Option<T>) {
+	if let Some(flag) = flag {
+		*value = flag.clone();
+	}
+}
+
+/// Replace an optional value with the flag, if it was given
+fn override_some<T: Clone>(value: &mut Option<T>, flag: &Option<T>) {
+	if flag.is_some() {
+		*value = flag.clone();
+	}
+}
@@ -0,0 +1,114 @@
+use crate::username::UsernameError;
+use actix::MailboxError;
+use actix_web::error::BlockingError;
+use actix_web::{http, http::StatusCode, HttpResponse, ResponseError};
+use coins_core::ErrorBody;
+use std::fmt;
+
+/// Every way a request can be refused, shared by the handlers and the actor.
+/// Sent as an [`ErrorBody`] with a matching status
+#[derive(Debug)]
+pub enum ApiError {
+	/// There is no session cookie, or we did not sign it
+	Unauthorized,
+	/// The admin's username and password were not given
+	AdminOnly,
+	/// The session is signed but this server has not seen the player log in
+	UnknownPlayer,
+	/// The coin is not a number, or there is no such coin
+	InvalidCoin(String),
+	/// The username was turned down at login
+	Username(UsernameError),
+	/// The player has used their flip budget
+	NoFlipsLeft,
+	/// The last round has ended
+	RoundOver,
+	/// The session or address has made too many requests lately
+	RateLimited,
+	/// A body or parameter could not be read
+	BadRequest(String),
+	/// There is no such endpoint
+	NotFound,
+	/// The server could not answer, e.g. the application stopped or rendering failed
+	Internal(String),
+}
+
+impl ApiError {
+	fn code(&self) -> &'static str {
+		match self {
+			ApiError::Unauthorized => "unauthorized",
+			ApiError::AdminOnly => "admin_only",
+			ApiError::UnknownPlayer => "unknown_player",
+			ApiError::InvalidCoin(_) => "invalid_coin",
+			ApiError::Username(e) => e.code(),
+			ApiError::NoFlipsLeft => "no_flips_left",
+			ApiError::RoundOver => "round_over",
+			ApiError::RateLimited => "rate_limited",
+			ApiError::BadRequest(_) => "bad_request",
+			ApiError::NotFound => "not_found",
+			ApiError::Internal(_) => "internal",
+		}
+	}
+}
+
+impl From<UsernameError> for ApiError {
+	fn from(e: UsernameError) -> Self {
+		ApiError::Username(e)
+	}
+}
+
+impl From<MailboxError> for ApiError {
+	fn from(e: MailboxError) -> Self {
+		ApiError::Internal(e.to_string())
+	}
+}
+
+impl<E: fmt::Debug> From<BlockingError<E>> for ApiError {
+	fn from(e: BlockingError<E>) -> Self {
+		ApiError::Internal(e.to_string())
+	}
+}
+
+impl fmt::Display for ApiError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ApiError::Unauthorized => write!(f, "Log in to get a session cookie"),
+			ApiError::AdminOnly => write!(f, "Only the admin may do this"),
+			ApiError::UnknownPlayer => write!(f, "This server does not know the player, log in again"),
+			ApiError::InvalidCoin(coin) => write!(f, "There is no coin \"{}\"", coin),
+			ApiError::Username(e) => write!(f, "{}", e),
+			ApiError::NoFlipsLeft => write!(f, "No flips left"),
+			ApiError::RoundOver => write!(f, "The last round is over"),
+			ApiError::RateLimited => write!(f, "Slow down! Too many flips, try again in a moment"),
+			ApiError::BadRequest(reason) => write!(f, "{}", reason),
+			ApiError::NotFound => write!(f, "There is no such endpoint"),
+			ApiError::Internal(reason) => write!(f, "The server could not answer: {}", reason),
+		}
+	}
+}
+
+impl ResponseError for ApiError {
+	fn status_code(&self) -> StatusCode {
+		match self {
+			ApiError::Unauthorized | ApiError::AdminOnly => StatusCode::UNAUTHORIZED,
+			ApiError::UnknownPlayer | ApiError::NotFound => StatusCode::NOT_FOUND,
+			ApiError::InvalidCoin(_) | ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+			ApiError::Username(UsernameError::Taken) => StatusCode::CONFLICT,
+			ApiError::Username(_) => StatusCode::BAD_REQUEST,
+			ApiError::NoFlipsLeft | ApiError::RoundOver => StatusCode::FORBIDDEN,
+			ApiError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+			ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+		}
+	}
+	fn error_response(&self) -> HttpResponse {
+		let mut response = HttpResponse::build(self.status_code());
+		if let ApiError::AdminOnly = self {
+			// has the browser ask for the username and password
+			response.header(http::header::WWW_AUTHENTICATE, "Basic realm=\"coins\"");
+		}
+		response.json(ErrorBody {
+			code: self.code().to_string(),
+			message: self.to_string(),
+		})
+	}
+}
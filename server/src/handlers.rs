@@ -1,54 +1,89 @@
-use crate::AppState;
-use actix::Addr;
-use actix_files::NamedFile;
-use actix_web::{
-	cookie, get, http, post, web, HttpMessage, HttpRequest, HttpResponse, Responder, Result,
-};
-use rand::{distributions::Bernoulli, rngs::ThreadRng};
-use rand_distr::Distribution;
-use std::path::PathBuf;
-use uuid::Uuid;
+use crate::admin::Credentials;
+use crate::app::RoundInfo;
+use crate::assets::Assets;
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::metrics::Mailbox;
+use crate::username::UsernameRules;
+use crate::{AppState, Sessions};
+use actix::{Addr, Handler, MailboxError, Message};
+use actix_web::{get, http, post, web, HttpRequest, HttpResponse, Result};
+use coins_core::{coin_css_color, CoinConfig, GameConfig};
 
 pub struct ApplicationState {
-	pub rng: ThreadRng,
 	pub addr: Addr<AppState>,
-	probabilities: Vec<Bernoulli>,
+	/// Shared by every worker
+	pub mailbox: Mailbox,
+	pub sessions: Sessions,
+	pub usernames: UsernameRules,
+	/// Who may flush and watch the replay
+	pub admin: Credentials,
+	/// The label of each coin
+	labels: Vec<String>,
+	room: String,
+	flip_budget: Option<usize>,
+	/// Where the login, game and view pages are served from
+	assets: Assets,
 }
 
 impl ApplicationState {
-	pub fn new(rng: ThreadRng, addr: Addr<AppState>, probabilities: Vec<f64>) -> Self {
+	pub fn new(
+		addr: Addr<AppState>,
+		mailbox: Mailbox,
+		sessions: Sessions,
+		usernames: UsernameRules,
+		config: &Config,
+	) -> Self {
 		ApplicationState {
-			rng,
 			addr,
-			probabilities: probabilities
-				.iter()
-				.map(|p| Bernoulli::new(*p).unwrap())
-				.collect::<Vec<Bernoulli>>(),
+			mailbox,
+			sessions,
+			usernames,
+			admin: Credentials::new(config.admin.clone()),
+			labels: (0..config.coins.len())
+				.map(|i| {
+					config
+						.labels
+						.get(i)
+						.cloned()
+						.unwrap_or_else(|| format!("Coin {}", i + 1))
+				})
+				.collect(),
+			room: config.room.clone(),
+			flip_budget: config.flip_budget,
+			assets: Assets::new(config.paths.assets.as_deref()),
 		}
 	}
-	pub fn get_bernoulli(&self, i: usize) -> Bernoulli {
-		*self
-			.probabilities
-			.get(i)
-			.unwrap_or(&Bernoulli::new(0.0).unwrap())
+
+	/// Send a message to the actor, counting it in the mailbox until it is answered
+	pub async fn send<M>(&self, msg: M) -> Result<M::Result, MailboxError>
+	where
+		M: Message + Send + 'static,
+		M::Result: Send,
+		AppState: Handler<M>,
+	{
+		let _pending = self.mailbox.track();
+		self.addr.send(msg).await
 	}
-}
 
-/// Set a cookie for 2 hours involving a uuid and the chosen name.
-/// Will overwrite any existing cookie.
-/// No redirecting
-#[get("/cookie/{id}")]
-pub async fn set_cookie(path: web::Path<String>) -> HttpResponse {
-	let cookie = (cookie::Cookie::build("id", Uuid::new_v4().to_string() + "_" + path.as_str()))
-		.max_age(time::Duration::hours(2))
-		.path("/")
-		.same_site(cookie::SameSite::Strict)
-		.finish();
-	let rep = HttpResponse::build(http::StatusCode::OK)
-		.cookie(cookie)
-		.content_type("plain/text")
-		.body("Set Cookie");
-	rep
+	/// The rules of the game as seen by the game page
+	pub fn game_config(&self, round: &RoundInfo) -> GameConfig {
+		GameConfig {
+			room: self.room.clone(),
+			coins: self
+				.labels
+				.iter()
+				.enumerate()
+				.map(|(i, label)| CoinConfig {
+					label: label.clone(),
+					color: coin_css_color(i),
+				})
+				.collect(),
+			flip_budget: self.flip_budget,
+			round: round.number,
+			round_end: round.end_millis(),
+		}
+	}
 }
 
 /// Redirects to the game page.
@@ -60,124 +95,109 @@ pub async fn redirect() -> HttpResponse {
 }
 
 /// Handling for game page
-pub async fn game_html() -> Result<NamedFile> {
-	Ok(NamedFile::open("../game/index.html")?)
+pub async fn game_html(req: HttpRequest, app_data: web::Data<ApplicationState>) -> Result<HttpResponse> {
+	app_data.assets.serve("game/index.html", &req)
 }
 
 /// Handling for static game path files
 #[get("/game/pkg/{filename}.{ext}")]
-pub async fn game_files(path: web::Path<(String, String)>) -> Result<NamedFile> {
+pub async fn game_files(
+	req: HttpRequest,
+	path: web::Path<(String, String)>,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse> {
 	let (filename, ext) = path.into_inner();
-	Ok(NamedFile::open(
-		("../game/pkg/".to_string() + &filename + "." + &ext)
-			.parse::<PathBuf>()
-			.unwrap(),
-	)?)
+	app_data
+		.assets
+		.serve(&format!("game/pkg/{}.{}", filename, ext), &req)
 }
 
 /// Handling for game styling
 #[get("/game/style/styles.css")]
-pub async fn game_style() -> Result<NamedFile> {
-	Ok(NamedFile::open(
-		"../game/style/styles.css"
-			.to_string()
-			.parse::<PathBuf>()
-			.unwrap(),
-	)?)
+pub async fn game_style(
+	req: HttpRequest,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse> {
+	app_data.assets.serve("game/style/styles.css", &req)
 }
 
 /// Handling for game styling
 #[get("/game/audio/{file}.mp3")]
-pub async fn game_audio(path: web::Path<String>) -> Result<NamedFile> {
-	let filename = path.into_inner();
-	Ok(NamedFile::open(
-		("../game/audio/".to_string() + &filename + ".mp3")
-			.parse::<PathBuf>()
-			.unwrap(),
-	)?)
+pub async fn game_audio(
+	req: HttpRequest,
+	path: web::Path<String>,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse> {
+	app_data
+		.assets
+		.serve(&format!("game/audio/{}.mp3", path.into_inner()), &req)
+}
+
+/// Handling for the view page, which replays the round so far
+pub async fn view_html(req: HttpRequest, app_data: web::Data<ApplicationState>) -> Result<HttpResponse> {
+	app_data.admin.check(&req)?;
+	app_data.assets.serve("view/index.html", &req)
+}
+
+/// Handling for static view path files
+#[get("/view/pkg/{filename}.{ext}")]
+pub async fn view_files(
+	req: HttpRequest,
+	path: web::Path<(String, String)>,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse> {
+	let (filename, ext) = path.into_inner();
+	app_data
+		.assets
+		.serve(&format!("view/pkg/{}.{}", filename, ext), &req)
+}
+
+/// Handling for view styling
+#[get("/view/style/styles.css")]
+pub async fn view_style(
+	req: HttpRequest,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse> {
+	app_data.assets.serve("view/style/styles.css", &req)
 }
 
 #[get("/")]
-pub async fn index() -> Result<NamedFile> {
-	Ok(NamedFile::open("../login/index.html")?)
+pub async fn index(req: HttpRequest, app_data: web::Data<ApplicationState>) -> Result<HttpResponse> {
+	app_data.assets.serve("login/index.html", &req)
 }
 
 /// Handling for game styling
 #[get("/pkg/{filename}.{ext}")]
-pub async fn index_files(path: web::Path<(String, String)>) -> Result<NamedFile> {
+pub async fn index_files(
+	req: HttpRequest,
+	path: web::Path<(String, String)>,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse> {
 	let (filename, ext) = path.into_inner();
-	Ok(NamedFile::open(
-		("../login/pkg/".to_string() + &filename + "." + &ext)
-			.parse::<PathBuf>()
-			.unwrap(),
-	)?)
+	app_data
+		.assets
+		.serve(&format!("login/pkg/{}.{}", filename, ext), &req)
 }
 /// Handling for game styling
 #[get("/style/styles.css")]
-pub async fn index_style() -> Result<NamedFile> {
-	Ok(NamedFile::open(
-		"../login/style/styles.css"
-			.to_string()
-			.parse::<PathBuf>()
-			.unwrap(),
-	)?)
-}
-
-/// Flip a coin using the thread rng handle. Send the result to application
-#[get("/flip/{coin}")]
-pub async fn flip(req: HttpRequest) -> impl Responder {
-	use crate::app::CoinFlipped;
-
-	let coin = req
-		.match_info()
-		.get("coin")
-		.map(|s| s.parse::<usize>().ok())
-		.flatten()
-		.unwrap_or(0); // if invalid, number defaults to first coin
-	let app_data = req.app_data::<web::Data<ApplicationState>>().unwrap();
-	let mut rng = app_data.rng.clone();
-	let addr = &app_data.addr;
-	let result: bool = app_data.get_bernoulli(coin).sample(&mut rng);
-	if let Some(user_id) = req.cookie("id") {
-		addr.do_send(CoinFlipped {
-			user_id: user_id.value().to_string(),
-			arm: coin,
-			result,
-		});
-		HttpResponse::build(http::StatusCode::OK)
-			.content_type("plain/text")
-			.body(format!("{}", result))
-	} else {
-		return HttpResponse::build(http::StatusCode::UNAUTHORIZED).finish();
-	}
-}
-
-/// Getting information for user to update their view
-#[get("/count")]
-pub async fn count(req: HttpRequest) -> impl Responder {
-	use crate::app::GetCount;
-	let id = req.cookie("id").unwrap().value().to_string();
-	let app_data = req.app_data::<web::Data<ApplicationState>>().unwrap();
-	let addr = &app_data.addr;
-	let count = addr
-		.send(GetCount { id: id.clone() })
-		.await
-		.expect("Failed to get count");
-	HttpResponse::build(http::StatusCode::OK)
-		.content_type("plain/text")
-		.body(format!("{}\n{}", count, id))
+pub async fn index_style(
+	req: HttpRequest,
+	app_data: web::Data<ApplicationState>,
+) -> Result<HttpResponse> {
+	app_data.assets.serve("login/style/styles.css", &req)
 }
 
-/// Send a message to the application to flush state into dump.json
+/// Send a message to the application to flush state into dump.cbor
 #[post("/flush")]
-pub async fn flush(req: HttpRequest) -> impl Responder {
+pub async fn flush(req: HttpRequest) -> Result<HttpResponse, ApiError> {
 	use crate::app::Flush;
 	let app_data = req.app_data::<web::Data<ApplicationState>>().unwrap();
+	app_data.admin.check(&req)?;
 	let addr = &app_data.addr;
 	addr.do_send(Flush {});
-	HttpResponse::Ok()
+	Ok(HttpResponse::Ok()
 		.content_type("plain/text")
-		.body("Sent Application message to flush")
+		.body("Sent Application message to flush"))
 }
 
 /// instead of 404 page, redirects to root
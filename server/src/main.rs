@@ -1,54 +1,122 @@
 use actix::Actor;
 use actix_web::{middleware, web, App, HttpServer};
 use clap::Clap;
-use rand::rngs::ThreadRng;
+
+mod api;
+
+mod admin;
 
 mod app;
+
+mod assets;
 use app::AppState;
 
 mod cli;
 use cli::Opts;
 
+mod config;
+use config::Config;
+
+mod error;
+
 mod handlers;
 use handlers::ApplicationState;
 
+mod metrics;
+use metrics::Mailbox;
+
+mod rate_limit;
+use rate_limit::RateLimit;
+
+mod session;
+use session::Sessions;
+
+mod tls;
+
+mod username;
+use username::UsernameRules;
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
 	let opts: Opts = Opts::parse();
-	if opts.coin_probs.len() < 3 {
-		println!("At least 3 coin probabilities are required");
-		std::process::exit(1);
+	let config = match Config::load(&opts) {
+		Ok(config) => config,
+		Err(e) => {
+			println!("{}", e);
+			std::process::exit(1);
+		}
+	};
+	let tls = match (&config.server.tls_cert, &config.server.tls_key) {
+		(Some(cert), Some(key)) => match tls::load(cert, key) {
+			Ok(tls) => Some(tls),
+			Err(e) => {
+				println!("{}", e);
+				std::process::exit(1);
+			}
+		},
+		_ => None,
+	};
+	let sessions = match &config.server.secret {
+		Some(secret) => Sessions::new(secret.as_bytes()),
+		None => Sessions::random(),
 	}
-	if opts.verbose {
+	.secure(tls.is_some());
+	let blocklist = match &config.paths.blocklist {
+		Some(path) => match std::fs::read_to_string(path) {
+			Ok(contents) => contents.lines().map(|line| line.to_string()).collect(),
+			Err(e) => {
+				println!("Could not read the blocklist {}: {}", path, e);
+				std::process::exit(1);
+			}
+		},
+		None => Vec::new(),
+	};
+	if config.verbose {
 		std::env::set_var("RUST_LOG", "actix_web=debug");
 		env_logger::init();
 		println!("Starting server.");
 	}
-	let app_addr =
-		AppState::new(ThreadRng::default(), opts.coin_probs.clone(), opts.verbose).start();
-	HttpServer::new(move || {
+	let app_addr = AppState::new(&config).start();
+	let rate_limit = RateLimit::new(
+		config.limits.session_flips_per_minute,
+		config.limits.ip_flips_per_minute,
+		sessions.clone(),
+		app_addr.clone(),
+	);
+	let mailbox = Mailbox::default();
+	let address = (config.server.host.clone(), config.server.port);
+	let scheme = if tls.is_some() { "https" } else { "http" };
+	let server = HttpServer::new(move || {
 		App::new()
 			.wrap(middleware::Logger::default())
 			.data(ApplicationState::new(
-				ThreadRng::default(),
 				app_addr.clone(),
-				opts.coin_probs.clone(),
+				mailbox.clone(),
+				sessions.clone(),
+				UsernameRules::new(blocklist.clone()),
+				&config,
 			))
-			.service(handlers::set_cookie)
 			.route("/game/", web::get().to(handlers::game_html))
 			.service(handlers::game_files)
 			.service(handlers::game_style)
 			.service(handlers::game_audio)
-			.service(handlers::flip)
+			.route("/view/", web::get().to(handlers::view_html))
+			.service(handlers::view_files)
+			.service(handlers::view_style)
 			.service(handlers::flush)
+			.service(metrics::healthz)
+			.service(metrics::metrics)
 			.service(handlers::redirect)
 			.service(handlers::index)
 			.service(handlers::index_files)
 			.service(handlers::index_style)
-			.service(handlers::count)
+			.service(api::scope(rate_limit.clone()))
 			.default_service(web::get().to(handlers::not_found))
-	})
-	.bind("0.0.0.0:8080")?
-	.run()
-	.await
+	});
+	let server = match tls {
+		Some(tls) => server.bind_rustls(&address, tls)?,
+		None => server.bind(&address)?,
+	};
+	println!("Listening on {}://{}:{}", scheme, address.0, address.1);
+	server.run().await
 }
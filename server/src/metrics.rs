@@ -0,0 +1,138 @@
+//! `/healthz` and `/metrics`, for watching a live session
+use crate::app::{GetMetrics, GetRound, Metrics};
+use crate::handlers::ApplicationState;
+use actix_web::{get, http, web, HttpResponse};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long the actor has to answer before the server counts as unhealthy
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Counts the messages sent to the actor that it has not answered yet, across every worker
+#[derive(Clone, Default)]
+pub struct Mailbox(Arc<AtomicUsize>);
+
+impl Mailbox {
+	pub fn depth(&self) -> usize {
+		self.0.load(Ordering::Relaxed)
+	}
+	/// Count a message as waiting until the returned guard is dropped, even if the request is
+	/// dropped before the answer arrives
+	pub fn track(&self) -> Pending {
+		self.0.fetch_add(1, Ordering::Relaxed);
+		Pending(self.0.clone())
+	}
+}
+
+pub struct Pending(Arc<AtomicUsize>);
+
+impl Drop for Pending {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+/// `200` while the actor answers, `503` once it stops
+#[get("/healthz")]
+pub async fn healthz(app_data: web::Data<ApplicationState>) -> HttpResponse {
+	let _pending = app_data.mailbox.track();
+	match app_data.addr.send(GetRound {}).timeout(HEALTH_TIMEOUT).await {
+		Ok(_) => HttpResponse::Ok().content_type("text/plain").body("ok"),
+		Err(_) => HttpResponse::build(http::StatusCode::SERVICE_UNAVAILABLE)
+			.content_type("text/plain")
+			.body("the game is not answering"),
+	}
+}
+
+/// Everything in the Prometheus text format, or `503` like `/healthz` if the actor cannot answer
+#[get("/metrics")]
+pub async fn metrics(app_data: web::Data<ApplicationState>) -> HttpResponse {
+	match app_data.send(GetMetrics {}).await {
+		Ok(snapshot) => HttpResponse::Ok()
+			.content_type("text/plain; version=0.0.4")
+			.body(prometheus(&snapshot, app_data.mailbox.depth())),
+		Err(_) => HttpResponse::build(http::StatusCode::SERVICE_UNAVAILABLE)
+			.content_type("text/plain")
+			.body("the game is not answering"),
+	}
+}
+
+/// Write out the metrics with their help and types
+fn prometheus(snapshot: &Metrics, mailbox: usize) -> String {
+	let mut out = String::new();
+	// each sample is what follows the name (labels or a suffix) and the value
+	let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+		writeln!(out, "# HELP {} {}", name, help).unwrap();
+		writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+		for (labels, value) in samples {
+			writeln!(out, "{}{} {}", name, labels, value).unwrap();
+		}
+	};
+	let plain = |value: String| vec![(String::new(), value)];
+	let by_coin = |f: fn(&(u64, u64)) -> u64| {
+		snapshot
+			.flips
+			.iter()
+			.enumerate()
+			.map(|(coin, flips)| (format!("{{coin=\"{}\"}}", coin), f(flips).to_string()))
+			.collect()
+	};
+	metric(
+		"coins_round",
+		"gauge",
+		"The current round, counting from 1.",
+		plain(snapshot.round.to_string()),
+	);
+	metric(
+		"coins_players",
+		"gauge",
+		"Players who have logged in.",
+		plain(snapshot.players.to_string()),
+	);
+	metric(
+		"coins_active_players",
+		"gauge",
+		"Players who flipped within the last minute.",
+		plain(snapshot.active_players.to_string()),
+	);
+	metric(
+		"coins_flips_total",
+		"counter",
+		"Flips by the players on each coin.",
+		by_coin(|(flips, _)| *flips),
+	);
+	metric(
+		"coins_heads_total",
+		"counter",
+		"Heads the players got on each coin.",
+		by_coin(|(_, heads)| *heads),
+	);
+	metric(
+		"coins_strategy_reward_total",
+		"counter",
+		"Heads each algorithm got.",
+		snapshot
+			.rewards
+			.iter()
+			.map(|(strategy, heads)| (format!("{{strategy=\"{}\"}}", strategy), heads.to_string()))
+			.collect(),
+	);
+	metric(
+		"coins_mailbox_depth",
+		"gauge",
+		"Messages sent to the game that it has not answered yet.",
+		plain(mailbox.to_string()),
+	);
+	metric(
+		"coins_dump_duration_seconds",
+		"summary",
+		"How long writing dumps took.",
+		vec![
+			("_sum".to_string(), snapshot.dump_seconds.to_string()),
+			("_count".to_string(), snapshot.dumps.to_string()),
+		],
+	);
+	out
+}
@@ -0,0 +1,214 @@
+use crate::app::{AppState, FlagPlayer};
+use crate::error::ApiError;
+use crate::session::{Sessions, COOKIE_NAME};
+use actix::Addr;
+use actix_service::{Service, Transform};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::{Error, HttpMessage};
+use futures_util::future::{ok, Either, Ready};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// How far back the limits look
+const WINDOW: Duration = Duration::from_secs(60);
+/// Forget keys that have gone quiet once there are this many
+const PRUNE_AT: usize = 1024;
+
+/// Middleware that turns requests away with `429` once a session or an IP address has made too
+/// many of them in the last minute. Sessions that hit their limit are flagged in the dump
+#[derive(Clone)]
+pub struct RateLimit {
+	limits: Arc<Mutex<Limits>>,
+	sessions: Sessions,
+	addr: Addr<AppState>,
+}
+
+struct Limits {
+	session: Window,
+	ip: Window,
+}
+
+/// The times of the recent requests for each key
+struct Window {
+	/// How many requests a key may make within [`WINDOW`]. 0 for no limit
+	limit: usize,
+	seen: HashMap<String, VecDeque<Instant>>,
+}
+
+impl Window {
+	fn new(limit: usize) -> Self {
+		Window {
+			limit,
+			seen: HashMap::new(),
+		}
+	}
+	/// Record a request for the key if it is under the limit
+	fn allow(&mut self, key: &str, now: Instant) -> bool {
+		if self.limit == 0 {
+			return true;
+		}
+		if self.seen.len() >= PRUNE_AT {
+			self.seen
+				.retain(|_, times| times.back().is_some_and(|t| now - *t < WINDOW));
+		}
+		let times = self.seen.entry(key.to_string()).or_default();
+		while times.front().is_some_and(|t| now - *t >= WINDOW) {
+			times.pop_front();
+		}
+		if times.len() >= self.limit {
+			return false;
+		}
+		times.push_back(now);
+		true
+	}
+}
+
+impl RateLimit {
+	/// Limits are per minute, 0 for no limit
+	pub fn new(
+		session_limit: usize,
+		ip_limit: usize,
+		sessions: Sessions,
+		addr: Addr<AppState>,
+	) -> Self {
+		RateLimit {
+			limits: Arc::new(Mutex::new(Limits {
+				session: Window::new(session_limit),
+				ip: Window::new(ip_limit),
+			})),
+			sessions,
+			addr,
+		}
+	}
+	/// Whether the request may go through. Requests without a valid session are only limited by
+	/// address, the handler turns them away anyway
+	fn allow(&self, req: &ServiceRequest) -> bool {
+		let now = Instant::now();
+		let ip = req.peer_addr().map(|addr| addr.ip());
+		let id = req
+			.cookie(COOKIE_NAME)
+			.and_then(|cookie| self.sessions.verify(cookie));
+		let mut limits = self.limits.lock().expect("Rate limits poisoned");
+		if let Some(ip) = ip {
+			if !limits.ip.allow(&ip.to_string(), now) {
+				return false;
+			}
+		}
+		if let Some(id) = id {
+			if !limits.session.allow(&id, now) {
+				self.addr.do_send(FlagPlayer {
+					id,
+					reason: "Hit the rate limit".to_string(),
+				});
+				return false;
+			}
+		}
+		true
+	}
+}
+
+impl<S, B> Transform<S> for RateLimit
+where
+	S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+	S::Future: 'static,
+	B: 'static,
+{
+	type Request = ServiceRequest;
+	type Response = ServiceResponse<B>;
+	type Error = Error;
+	type InitError = ();
+	type Transform = RateLimitMiddleware<S>;
+	type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+	fn new_transform(&self, service: S) -> Self::Future {
+		ok(RateLimitMiddleware {
+			service,
+			rate_limit: self.clone(),
+		})
+	}
+}
+
+pub struct RateLimitMiddleware<S> {
+	service: S,
+	rate_limit: RateLimit,
+}
+
+impl<S, B> Service for RateLimitMiddleware<S>
+where
+	S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+	S::Future: 'static,
+	B: 'static,
+{
+	type Request = ServiceRequest;
+	type Response = ServiceResponse<B>;
+	type Error = Error;
+	type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.service.poll_ready(cx)
+	}
+
+	fn call(&mut self, req: ServiceRequest) -> Self::Future {
+		if self.rate_limit.allow(&req) {
+			Either::Left(self.service.call(req))
+		} else {
+			Either::Right(ok(req.error_response(ApiError::RateLimited)))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn turns_away_requests_over_the_limit() {
+		let mut window = Window::new(2);
+		let now = Instant::now();
+		assert!(window.allow("a", now));
+		assert!(window.allow("a", now));
+		assert!(!window.allow("a", now));
+		// other keys have their own limit
+		assert!(window.allow("b", now));
+	}
+
+	#[test]
+	fn forgets_requests_older_than_the_window() {
+		let mut window = Window::new(1);
+		let now = Instant::now();
+		assert!(window.allow("a", now));
+		assert!(!window.allow("a", now + WINDOW / 2));
+		assert!(window.allow("a", now + WINDOW));
+	}
+
+	#[test]
+	fn turned_away_requests_do_not_count() {
+		let mut window = Window::new(1);
+		let now = Instant::now();
+		assert!(window.allow("a", now));
+		assert!(!window.allow("a", now + WINDOW / 2));
+		// only the first request is within the window of this one
+		assert!(window.allow("a", now + WINDOW));
+		assert!(!window.allow("a", now + WINDOW + WINDOW / 2));
+	}
+
+	#[test]
+	fn zero_is_no_limit() {
+		let mut window = Window::new(0);
+		let now = Instant::now();
+		assert!((0..1000).all(|_| window.allow("a", now)));
+	}
+
+	#[test]
+	fn prunes_quiet_keys() {
+		let mut window = Window::new(1);
+		let now = Instant::now();
+		for i in 0..PRUNE_AT {
+			assert!(window.allow(&i.to_string(), now));
+		}
+		assert!(window.allow("late", now + WINDOW));
+		assert_eq!(window.seen.len(), 1);
+	}
+}
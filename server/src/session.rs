@@ -0,0 +1,111 @@
+use actix_web::cookie::{Cookie, CookieJar, Key, SameSite};
+use actix_web::{HttpMessage, HttpRequest};
+
+/// The name of the cookie holding the player's id
+pub const COOKIE_NAME: &str = "id";
+
+/// Signs the session cookies handed out at login and checks them on every request,
+/// so a player cannot edit their cookie to become someone else
+#[derive(Clone)]
+pub struct Sessions {
+	key: Key,
+	/// Whether the cookies are only sent over HTTPS
+	secure: bool,
+}
+
+impl Sessions {
+	/// Derive the signing key from a secret of at least 32 bytes
+	pub fn new(secret: &[u8]) -> Self {
+		Sessions {
+			key: Key::derive_from(secret),
+			secure: false,
+		}
+	}
+	/// Sign with a random key, so sessions do not survive a restart
+	pub fn random() -> Self {
+		Sessions {
+			key: Key::generate(),
+			secure: false,
+		}
+	}
+	/// Only send the cookies over HTTPS, for a server with TLS
+	pub fn secure(self, secure: bool) -> Self {
+		Sessions { secure, ..self }
+	}
+	/// A signed cookie for 2 hours holding the player's id
+	pub fn cookie(&self, id: String) -> Cookie<'static> {
+		let cookie = Cookie::build(COOKIE_NAME, id)
+			.max_age(time::Duration::hours(2))
+			.path("/")
+			.same_site(SameSite::Strict)
+			.http_only(true)
+			.secure(self.secure)
+			.finish();
+		let mut jar = CookieJar::new();
+		jar.signed(&self.key).add(cookie);
+		jar.get(COOKIE_NAME)
+			.cloned()
+			.expect("The cookie was just added")
+	}
+	/// The id of the player making the request, if their cookie is present and was signed by us
+	pub fn player_id(&self, req: &HttpRequest) -> Option<String> {
+		self.verify(req.cookie(COOKIE_NAME)?)
+	}
+	/// The id in a session cookie, if we signed it
+	pub fn verify(&self, cookie: Cookie<'static>) -> Option<String> {
+		let mut jar = CookieJar::new();
+		jar.add_original(cookie);
+		jar.signed(&self.key)
+			.get(COOKIE_NAME)
+			.map(|cookie| cookie.value().to_string())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SECRET: &[u8] = b"a secret that is at least 32 bytes long";
+
+	#[test]
+	fn reads_back_the_id_it_signed() {
+		let sessions = Sessions::new(SECRET);
+		let cookie = sessions.cookie("uuid_jane".to_string());
+		assert_ne!(cookie.value(), "uuid_jane");
+		assert_eq!(sessions.verify(cookie), Some("uuid_jane".to_string()));
+	}
+
+	#[test]
+	fn keeps_sessions_with_the_same_secret() {
+		let cookie = Sessions::new(SECRET).cookie("uuid_jane".to_string());
+		assert_eq!(
+			Sessions::new(SECRET).verify(cookie),
+			Some("uuid_jane".to_string())
+		);
+	}
+
+	#[test]
+	fn turns_down_cookies_signed_with_another_key() {
+		let cookie = Sessions::random().cookie("uuid_jane".to_string());
+		assert_eq!(Sessions::new(SECRET).verify(cookie), None);
+	}
+
+	#[test]
+	fn turns_down_tampered_cookies() {
+		let sessions = Sessions::new(SECRET);
+		let signed = sessions.cookie("uuid_jane".to_string());
+		let tampered = signed.value().replace("jane", "john");
+		assert_eq!(sessions.verify(Cookie::new(COOKIE_NAME, tampered)), None);
+		assert_eq!(sessions.verify(Cookie::new(COOKIE_NAME, "uuid_john")), None);
+	}
+
+	#[test]
+	fn marks_the_cookie_secure_only_when_asked() {
+		let id = || "uuid_jane".to_string();
+		assert_eq!(Sessions::random().cookie(id()).secure(), Some(false));
+		assert_eq!(
+			Sessions::random().secure(true).cookie(id()).secure(),
+			Some(true)
+		);
+	}
+}
@@ -0,0 +1,33 @@
+use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rustls::{NoClientAuth, ServerConfig};
+use std::fs::File;
+use std::io::BufReader;
+
+/// Load a PEM certificate chain and a PKCS #8 or RSA private key for serving HTTPS
+pub fn load(cert_path: &str, key_path: &str) -> Result<ServerConfig, String> {
+	let open = |path: &str| {
+		File::open(path)
+			.map(BufReader::new)
+			.map_err(|e| format!("Could not open {}: {}", path, e))
+	};
+	let cert_chain = certs(&mut open(cert_path)?)
+		.map_err(|_| format!("Could not read certificates from {}", cert_path))?;
+	if cert_chain.is_empty() {
+		return Err(format!("There are no certificates in {}", cert_path));
+	}
+	let mut keys = pkcs8_private_keys(&mut open(key_path)?)
+		.map_err(|_| format!("Could not read a private key from {}", key_path))?;
+	if keys.is_empty() {
+		keys = rsa_private_keys(&mut open(key_path)?)
+			.map_err(|_| format!("Could not read a private key from {}", key_path))?;
+	}
+	let key = keys
+		.into_iter()
+		.next()
+		.ok_or_else(|| format!("There is no private key in {}", key_path))?;
+	let mut config = ServerConfig::new(NoClientAuth::new());
+	config
+		.set_single_cert(cert_chain, key)
+		.map_err(|e| format!("The certificate and key do not work together: {}", e))?;
+	Ok(config)
+}
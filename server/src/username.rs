@@ -0,0 +1,164 @@
+use std::fmt;
+
+const MIN_LENGTH: usize = 2;
+const MAX_LENGTH: usize = 20;
+/// Names that could be mistaken for the server or the algorithms
+const RESERVED: [&str; 7] = [
+	"admin", "server", "thompson", "ucb", "naive", "algorithm", "player",
+];
+
+/// The rules a username has to follow to get a cookie
+pub struct UsernameRules {
+	/// Lowercase words that may not appear anywhere in a name
+	blocklist: Vec<String>,
+}
+
+impl UsernameRules {
+	/// Blank lines and lines starting with `#` in the blocklist are ignored
+	pub fn new(blocklist: Vec<String>) -> Self {
+		UsernameRules {
+			blocklist: blocklist
+				.into_iter()
+				.map(|word| word.trim().to_lowercase())
+				.filter(|word| !word.is_empty() && !word.starts_with('#'))
+				.collect(),
+		}
+	}
+	/// Check everything but uniqueness, which only the actor knows about
+	pub fn check(&self, name: &str) -> Result<(), UsernameError> {
+		let length = name.chars().count();
+		if length < MIN_LENGTH {
+			return Err(UsernameError::TooShort);
+		}
+		if length > MAX_LENGTH {
+			return Err(UsernameError::TooLong);
+		}
+		// `_` separates the uuid from the name in the player's id
+		if let Some(c) = name
+			.chars()
+			.find(|c| !(c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '.'))
+		{
+			return Err(UsernameError::InvalidCharacter(c));
+		}
+		let lowercase = name.to_lowercase();
+		if RESERVED.contains(&lowercase.as_str()) {
+			return Err(UsernameError::Reserved);
+		}
+		if self
+			.blocklist
+			.iter()
+			.any(|word| lowercase.contains(word.as_str()))
+		{
+			return Err(UsernameError::Blocked);
+		}
+		Ok(())
+	}
+}
+
+/// Why a username was turned down
+#[derive(Debug)]
+pub enum UsernameError {
+	TooShort,
+	TooLong,
+	InvalidCharacter(char),
+	Reserved,
+	Blocked,
+	/// Someone in the room already has the name
+	Taken,
+}
+
+impl UsernameError {
+	pub fn code(&self) -> &'static str {
+		match self {
+			UsernameError::TooShort => "username_too_short",
+			UsernameError::TooLong => "username_too_long",
+			UsernameError::InvalidCharacter(_) => "username_invalid_character",
+			UsernameError::Reserved => "username_reserved",
+			UsernameError::Blocked => "username_blocked",
+			UsernameError::Taken => "username_taken",
+		}
+	}
+}
+
+impl fmt::Display for UsernameError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			UsernameError::TooShort => {
+				write!(f, "Usernames need at least {} characters", MIN_LENGTH)
+			}
+			UsernameError::TooLong => write!(f, "Usernames can be at most {} characters", MAX_LENGTH),
+			UsernameError::InvalidCharacter(c) => write!(
+				f,
+				"\"{}\" is not allowed. Use letters, numbers, spaces, '-' and '.'",
+				c
+			),
+			UsernameError::Reserved => write!(f, "That username is reserved"),
+			UsernameError::Blocked => write!(f, "That username is not allowed"),
+			UsernameError::Taken => write!(f, "Someone in this room already has that username"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rules() -> UsernameRules {
+		UsernameRules::new(vec![
+			"# a comment".to_string(),
+			"".to_string(),
+			"  Rude ".to_string(),
+		])
+	}
+
+	#[test]
+	fn accepts_names_within_the_rules() {
+		for name in ["ab", "Jane Doe", "j.doe-2", "Zoë", "abcdefghijklmnopqrst"] {
+			assert!(rules().check(name).is_ok(), "{}", name);
+		}
+	}
+
+	#[test]
+	fn checks_the_length_in_characters() {
+		assert!(matches!(rules().check("a"), Err(UsernameError::TooShort)));
+		assert!(matches!(rules().check(""), Err(UsernameError::TooShort)));
+		assert!(matches!(
+			rules().check("abcdefghijklmnopqrstu"),
+			Err(UsernameError::TooLong)
+		));
+		// 20 characters but more bytes
+		assert!(rules().check(&"é".repeat(20)).is_ok());
+	}
+
+	#[test]
+	fn turns_down_invalid_characters() {
+		assert!(matches!(
+			rules().check("jane_doe"),
+			Err(UsernameError::InvalidCharacter('_'))
+		));
+		assert!(matches!(
+			rules().check("<script>"),
+			Err(UsernameError::InvalidCharacter('<'))
+		));
+	}
+
+	#[test]
+	fn turns_down_reserved_names_in_any_case() {
+		assert!(matches!(
+			rules().check("Admin"),
+			Err(UsernameError::Reserved)
+		));
+		assert!(matches!(rules().check("UCB"), Err(UsernameError::Reserved)));
+		assert!(rules().check("admins").is_ok());
+	}
+
+	#[test]
+	fn turns_down_blocked_words_anywhere() {
+		assert!(matches!(
+			rules().check("so RUDE"),
+			Err(UsernameError::Blocked)
+		));
+		// comments and blank lines are not words
+		assert!(rules().check("a comment").is_ok());
+	}
+}
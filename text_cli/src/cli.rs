@@ -0,0 +1,62 @@
+use clap::{AppSettings, Clap};
+use coins_core::{Metric, Ranking, Selection};
+
+/// Render a dump into the shapes the view page draws
+#[derive(Clap, Clone)]
+#[clap(setting = AppSettings::ColoredHelp)]
+pub struct Opts {
+	/// The dump to render, as written by the server. CBOR or JSON, told apart by its contents
+	#[clap(default_value = "../server/dump.cbor")]
+	pub input: String,
+	/// Where the rendered dump is written
+	#[clap(short, long, default_value = "rendered_dump.cbor")]
+	pub output: String,
+	/// The first step to render
+	#[clap(long, default_value = "0")]
+	pub start: usize,
+	/// Render up to but not including this step. Up to the end of the dump if not given
+	#[clap(long)]
+	pub end: Option<usize>,
+	/// Only render every nth step. The last step is always rendered
+	#[clap(long, default_value = "1")]
+	pub stride: usize,
+	/// How many points are sampled along each beta distribution, half of them around its peak
+	#[clap(long, default_value = "80")]
+	pub resolution: usize,
+	/// Write the flips themselves for the view to replay, instead of the shapes for every step.
+	/// Far smaller, and the view renders each step as it is shown
+	#[clap(long)]
+	pub compact: bool,
+	/// A TOML file placing the boxes and the naive bars, and coloring the coins. The view page
+	/// draws everything else where the default layout has it
+	#[clap(long)]
+	pub layout: Option<String>,
+	/// The id or username of a player to render. Given more than once for more players
+	#[clap(short, long, number_of_values = 1, conflicts_with = "rank")]
+	pub player: Vec<String>,
+	/// Render the player at this rank under the metric, counting from 1. Given more than once for
+	/// more players. The best player if no players are given
+	#[clap(long, number_of_values = 1)]
+	pub rank: Vec<usize>,
+	/// Render every player
+	#[clap(long, conflicts_with_all = &["player", "rank"])]
+	pub all: bool,
+	/// What players are ranked by: score (the most heads), proportion (of heads) or regret (the
+	/// least regret per flip)
+	#[clap(long, default_value = "proportion")]
+	pub metric: Metric,
+	/// Players with fewer flips rank below everyone with enough
+	#[clap(long, default_value = "10")]
+	pub min_flips: usize,
+}
+
+impl Opts {
+	/// The players asked for
+	pub fn selection(&self) -> Selection {
+		let ranking = Ranking {
+			metric: self.metric,
+			min_flips: self.min_flips,
+		};
+		Selection::new(self.all, &self.player, &self.rank, ranking)
+	}
+}
@@ -1,32 +1,51 @@
-mod utils;
+mod cli;
 
+use clap::Clap;
+use cli::Opts;
+use coins_core::render::render_dump;
+use coins_core::{CompactRendered, Dump, Layout, Steps};
 use std::fs::File;
-use utils::*;
 
 fn main() {
-    // get number of iterations from args
-    let args: Vec<String> = std::env::args().collect();
-    let iterations = if args.len() > 1 {
-        args[1].parse::<usize>().unwrap()
-    } else {
-        println!("No iterations given, using default of 100");
-        100
-    };
+    let opts = Opts::parse();
+    if opts.stride == 0 || opts.resolution < 2 {
+        println!("The stride must be at least 1 and the resolution at least 2");
+        std::process::exit(1);
+    }
 
-    let data = Dump::load("../server/dump.cbor").to_filtered();
-    let mut state = RenderState::new();
+    let layout = opts
+        .layout
+        .as_deref()
+        .map_or_else(|| Ok(Layout::default()), Layout::read)
+        .unwrap_or_else(|e| exit(&e));
+    if layout.has_anchors() {
+        exit("Anchors are found in a template, which only the image CLI draws. Give positions instead");
+    }
 
-    // iterate for the longest number of turns taken. thompson, ucb, and naive should all have the same length
-    let total_iterations = usize::min(data.thompson.len().max(data.best_player.len()), iterations);
-    let mut output = RenderedStateContainer::new(total_iterations, data.best_player_name.clone());
+    let dump = Dump::read(&opts.input).unwrap_or_else(|e| exit(&e));
+    let players = dump
+        .select(&opts.selection())
+        .unwrap_or_else(|e| exit(&e));
+    // every chosen player is rendered into each state, for the view to pick between
+    let data = dump.filter(&players).unwrap_or_else(|e| exit(&e));
+    let steps = Steps {
+        start: opts.start,
+        end: opts.end,
+        stride: opts.stride,
+    };
 
-    for i in 0..total_iterations {
-        // update what each algorithm sees
-        state.update(&data, i);
-        // render to the state
-        state.render(&mut output.state[i]);
-    }
     // use serde to dump information
-    let file = File::create("rendered_dump.cbor").expect("Could not open output file");
-    serde_cbor::to_writer(file, &output).expect("Could not write to output file");
+    let file = File::create(&opts.output).expect("Could not open output file");
+    if opts.compact {
+        let output = CompactRendered::new(data, steps, opts.resolution, &layout);
+        serde_cbor::to_writer(file, &output).expect("Could not write to output file");
+    } else {
+        let output = render_dump(&data, &steps, opts.resolution, &layout);
+        serde_cbor::to_writer(file, &output).expect("Could not write to output file");
+    }
+}
+
+fn exit(message: &str) -> ! {
+    println!("{}", message);
+    std::process::exit(1);
 }
@@ -1,40 +1,17 @@
+use coins_core::chart::svg_path;
+use coins_core::{
+    css_color, naive_status, Align, Chart, Rectangle, Rendered, RenderedState, NAIVE_PANEL,
+};
 use log::Level;
 use mogwai::prelude::*;
 use std::panic;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
 use web_sys::console::log;
-use web_sys::{HtmlInputElement, KeyboardEvent};
-
-#[derive(serde::Deserialize, Default)]
-struct RenderedStateContainer {
-    #[allow(clippy::vec_box)]
-    // box so that no cloning is performed => huge performance boost
-    state: Vec<Box<RenderedState>>,
-    best_player_name: String,
-}
-
-#[derive(Clone, serde::Deserialize)]
-struct RenderedState {
-    thompson_rects: (Rectangle, Rectangle, Rectangle),
-    ucb_rects: (Rectangle, Rectangle, Rectangle),
-    naive_rects: (Rectangle, Rectangle, Rectangle),
-    player_rects: (Rectangle, Rectangle, Rectangle),
-    thompson_counts: (usize, usize),
-    ucb_counts: (usize, usize),
-    naive_counts: (usize, usize),
-    player_counts: (usize, usize),
-    thompson_paths: (String, String, String),
-    ucb_paths: (String, String, String),
-}
-
-#[derive(Clone, serde::Deserialize)]
-struct Rectangle {
-    x: f64,
-    y: f64,
-    width: f64,
-    height: f64,
-}
+use web_sys::{
+    HtmlInputElement, HtmlSelectElement, KeyboardEvent, Request, RequestInit, RequestMode, Response,
+};
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
@@ -45,23 +22,59 @@ macro_rules! console_log {
 }
 
 struct Viewer {
-    data: RenderedStateContainer,
+    /// Either format of rendered dump, compact ones rendered a step at a time
+    data: Rendered,
     index: usize,
+    /// Which of the rendered players is shown
+    player: usize,
 }
 
 #[derive(Clone)]
 enum ViewerIn {
     Input(Vec<u8>),
+    /// Render the round so far on the server that served this page
+    Load,
     Forward,
     Backward,
+    /// Show another of the rendered players
+    Player(usize),
     None,
 }
 
 #[derive(Clone)]
 enum ViewerOut {
     Update(Box<RenderedState>),
+    /// The css color of each coin in the loaded data, sent before the first update
+    Coins(Vec<String>),
     NameLength((String, usize)),
     Index(usize),
+    /// The names of the players to pick between, sent before the first update
+    Players(Vec<String>),
+    /// The time series of the chosen player's session, sent before the update showing them
+    Charts(Vec<Chart>),
+}
+
+impl Viewer {
+    /// The current step, with the chosen player in the player's column
+    fn current(&mut self) -> Box<RenderedState> {
+        let mut state = self.data.state(self.index);
+        if let Some(player) = state.players.get(self.player).cloned() {
+            state.player_rects = player.rects;
+            state.player_counts = player.counts;
+        }
+        state
+    }
+    /// Ids are the player's name after a uuid
+    fn name(&self) -> String {
+        let names = self.data.player_names();
+        names
+            .get(self.player)
+            .copied()
+            .unwrap_or_else(|| self.data.best_player_name())
+            .split_once('_')
+            .map_or("", |(_, name)| name)
+            .to_string()
+    }
 }
 
 impl Component for Viewer {
@@ -73,40 +86,87 @@ impl Component for Viewer {
         &mut self,
         msg: &ViewerIn,
         tx_view: &Transmitter<ViewerOut>,
-        _subscriber: &Subscriber<ViewerIn>,
+        subscriber: &Subscriber<ViewerIn>,
     ) {
         match msg {
             ViewerIn::Input(data_string) => {
-                if let Ok(data) = serde_cbor::from_slice::<RenderedStateContainer>(&data_string) {
-                    self.index = 0;
-                    self.data = data;
-                    tx_view.send(&ViewerOut::Update(self.data.state[self.index].clone()));
-                    tx_view.send(&ViewerOut::NameLength((
-                        self.data
-                            .best_player_name
-                            .splitn(2, '_')
-                            .collect::<Vec<&str>>()[1]
-                            .to_string(),
-                        self.data.state.len(),
-                    )));
-                } else {
-                    console_log!("Could not parse output.");
+                match Rendered::from_slice(data_string) {
+                    Ok(data) if data.is_empty() => {
+                        console_log!("Nothing has been flipped yet.");
+                    }
+                    Ok(data) => {
+                        self.index = 0;
+                        self.player = 0;
+                        self.data = data;
+                        tx_view.send(&ViewerOut::Coins(
+                            (0..self.data.coins()).map(|coin| self.data.css_color(coin)).collect(),
+                        ));
+                        tx_view.send(&ViewerOut::Players(
+                            self.data
+                                .player_names()
+                                .iter()
+                                .map(|id| id.split_once('_').map_or("", |(_, name)| name).to_string())
+                                .collect(),
+                        ));
+                        tx_view.send(&ViewerOut::Charts(self.data.charts(self.player)));
+                        tx_view.send(&ViewerOut::Update(self.current()));
+                        tx_view.send(&ViewerOut::NameLength((self.name(), self.data.len())));
+                    }
+                    Err(e) => {
+                        console_log!("Could not parse output. {}", e);
+                    }
                 }
             }
+            ViewerIn::Load => {
+                let opts = RequestInit::new();
+                opts.set_method("GET");
+                opts.set_mode(RequestMode::SameOrigin);
+                let req = Request::new_with_str_and_init("/api/v1/rendered?compact=true", &opts)
+                    .expect("Failed to create request");
+                let (tx, rx) = txrx();
+                tx.send_async(async move {
+                    let window = web_sys::window().unwrap();
+                    // not served by the game server, so a file has to be picked instead
+                    let response = match JsFuture::from(window.fetch_with_request(&req)).await {
+                        Ok(response) => response.dyn_into::<Response>().expect("Malformed response"),
+                        Err(_) => return ViewerIn::None,
+                    };
+                    if response.status() != 200 {
+                        console_log!("Could not load the current round, pick a file instead.");
+                        return ViewerIn::None;
+                    }
+                    let buf_val = JsFuture::from(response.array_buffer().unwrap())
+                        .await
+                        .unwrap();
+                    let typebuf: js_sys::Uint8Array = js_sys::Uint8Array::new(&buf_val);
+                    let mut body = vec![0; typebuf.length() as usize];
+                    typebuf.copy_to(&mut body[..]);
+                    ViewerIn::Input(body)
+                });
+                subscriber.subscribe(&rx);
+            }
             ViewerIn::Forward => {
-                if self.index + 1 < self.data.state.len() {
+                if self.index + 1 < self.data.len() {
                     self.index += 1;
-                    tx_view.send(&ViewerOut::Update(self.data.state[self.index].clone()));
+                    tx_view.send(&ViewerOut::Update(self.current()));
                     tx_view.send(&ViewerOut::Index(self.index));
                 }
             }
             ViewerIn::Backward => {
                 if self.index > 0 {
                     self.index -= 1;
-                    tx_view.send(&ViewerOut::Update(self.data.state[self.index].clone()));
+                    tx_view.send(&ViewerOut::Update(self.current()));
                     tx_view.send(&ViewerOut::Index(self.index));
                 }
             }
+            ViewerIn::Player(player) => {
+                if *player < self.data.player_names().len() && !self.data.is_empty() {
+                    self.player = *player;
+                    tx_view.send(&ViewerOut::Charts(self.data.charts(self.player)));
+                    tx_view.send(&ViewerOut::Update(self.current()));
+                    tx_view.send(&ViewerOut::NameLength((self.name(), self.data.len())));
+                }
+            }
             ViewerIn::None => {}
         }
     }
@@ -129,6 +189,36 @@ impl Component for Viewer {
             ViewerOut::Update(data) => Some(data.clone()),
             _ => None,
         });
+        let rx_shapes = {
+            let rx_data = rx_data.branch();
+            rx.branch_filter_map(move |msg: &ViewerOut| match msg {
+                ViewerOut::Coins(colors) => Some(Patch::Replace {
+                    index: 0,
+                    value: View::from(coin_shapes(colors, &rx_data)),
+                }),
+                _ => None,
+            })
+        };
+        let rx_picker = {
+            let tx = tx.clone();
+            rx.branch_filter_map(move |msg: &ViewerOut| match msg {
+                ViewerOut::Players(names) => Some(Patch::Replace {
+                    index: 0,
+                    value: View::from(player_picker(names, &tx)),
+                }),
+                _ => None,
+            })
+        };
+        let rx_charts = {
+            let rx_data = rx_data.branch();
+            rx.branch_filter_map(move |msg: &ViewerOut| match msg {
+                ViewerOut::Charts(charts) => Some(Patch::Replace {
+                    index: 0,
+                    value: View::from(chart_view(charts, &rx_data)),
+                }),
+                _ => None,
+            })
+        };
         let rx_index = rx.branch_filter_map(|msg: &ViewerOut| match msg {
             ViewerOut::Index(index) => Some(format!(" {}/", index.clone())),
             _ => None,
@@ -164,6 +254,7 @@ impl Component for Viewer {
             _ => ViewerIn::None,
         });
         let tx_forward = tx.contra_map(|_| ViewerIn::Forward);
+        let tx_load = tx.contra_map(|_| ViewerIn::Load);
         let tx_backward = tx.contra_map(|_| ViewerIn::Backward);
 
         let ns = "http://www.w3.org/2000/svg";
@@ -182,14 +273,10 @@ impl Component for Viewer {
                     <g clip-path= "url(#clipPath2)" xmlns=ns>
                         <path fill= "rgba(0,0,0,0)" stroke= "rgba(255,0,0,255)" stroke-width= "0.005" transform= "matrix(52.92 0 0 -52.92 116.445 232.815)" d= "M 0 0 L 1 1 L 1 0 L 0 0 L 0 1 L 1 1 Z" />
                     </g>
-                    // ? the paths for thompson
-                    <path d={rx_data.branch_map(|m| m.thompson_paths.0.clone())} class="graph" style="stroke:rgb(0,157,255);fill:none;" stroke-width="0.02" transform="matrix(52.92 0.0 0.0 -26.46 31.76225 232.815)" xmlns=ns/>
-                    <path d={rx_data.branch_map(|m| m.thompson_paths.1.clone())} class="graph" style="stroke:rgb(255,95,89);fill:none;" stroke-width="0.02" transform="matrix(52.92 0.0 0.0 -26.46 31.76225 232.815)" xmlns=ns/>
-                    <path d={rx_data.branch_map(|m| m.thompson_paths.2.clone())} class="graph" style="stroke:rgb(0,176,89);fill:none;" stroke-width="0.02" transform="matrix(52.92 0.0 0.0 -26.46 31.76225 232.815)" xmlns=ns/>
-                    // ? the paths for ucb
-                    <path d={rx_data.branch_map(|m| m.ucb_paths.0.clone())} class="graph" style="stroke:rgb(0,157,255);fill:none;" stroke-width="0.02" transform="matrix(26.46 0.0 0.0 -52.92 116.445 232.815)" xmlns=ns/>
-                    <path d={rx_data.branch_map(|m| m.ucb_paths.1.clone())} class="graph" style="stroke:rgb(255,95,89);fill:none;" stroke-width="0.02" transform="matrix(26.46 0.0 0.0 -52.92 116.445 232.815)" xmlns=ns/>
-                    <path d={rx_data.branch_map(|m| m.ucb_paths.2.clone())} class="graph" style="stroke:rgb(0,176,89);fill:none;" stroke-width="0.02" transform="matrix(26.46 0.0 0.0 -52.92 116.445 232.815)" xmlns=ns/>
+                    // ? the paths for thompson and ucb, and the rectangles, once the number of coins is known
+                    <g patch:children=rx_shapes xmlns=ns>
+                        <g xmlns=ns></g>
+                    </g>
                     // ? horizontal lines below titles
                     <path id= "path975" fill= "none" stroke= "rgba(29,0,255,255)" stroke-width= "1.165" d= "M 26.458333 26.458333 L 52.916666 26.458333"  xmlns=ns/>
                     <path id= "path977" fill= "none" stroke= "rgba(0,0,0,255)" stroke-width= "0.264583" d= "M 68.791666 26.458333 L 95.249999 26.458333"  xmlns=ns/>
@@ -221,47 +308,169 @@ impl Component for Viewer {
                     <text xml:space="preserve" style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x="84.666664" y="238.125" id="text1903-1" xmlns=ns><tspan id="tspan1901-6" x="84.666664" y="238.125" style="stroke-width:0.264583" xmlns=ns>{"1"}</tspan></text>
                     <text xml:space="preserve" style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x="29.104166" y="182.5625" id="text1691" xmlns=ns><tspan  id="tspan1689" x="29.104166" y="182.5625" style="stroke-width:0.264583" xmlns=ns>{"2"}</tspan></text>
                     <text xml:space="preserve" style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x="84.666664" y="238.125" id="text1903-1" transform="translate(84.667335)" xmlns=ns><tspan id="tspan1901-6" x="84.666664" y="238.125" style="stroke-width:0.264583" xmlns=ns>{"2"}</tspan></text>
-                    // ? the rectangles
-                    <rect id="player-1" style="fill:#009dff;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.thompson_rects.0.width)))} height={("42.333351", rx_data.branch_map(|m| format!("{}", m.thompson_rects.0.height)))} x={("153.45833", rx_data.branch_map(|m| format!("{}", m.thompson_rects.0.x)))} y={("37.041672", rx_data.branch_map(|m| format!("{}", m.thompson_rects.0.y)))} xmlns=ns/>
-                    <rect id="player-2" style="fill:#ff5f59;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.thompson_rects.1.width)))} height={("42.333355", rx_data.branch_map(|m| format!("{}", m.thompson_rects.1.height)))} x={("153.45833", rx_data.branch_map(|m| format!("{}", m.thompson_rects.1.x)))} y={("79.375023", rx_data.branch_map(|m| format!("{}", m.thompson_rects.1.y)))} xmlns=ns/>
-                    <rect id="player-3" style="fill:#00b059;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.thompson_rects.2.width)))} height={("21.166685", rx_data.branch_map(|m| format!("{}", m.thompson_rects.2.height)))} x={("153.45833", rx_data.branch_map(|m| format!("{}", m.thompson_rects.2.x)))} y={("121.70834", rx_data.branch_map(|m| format!("{}", m.thompson_rects.2.y)))} xmlns=ns/>
-                    <rect id="ucb-1" style="fill:#009dff;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.naive_rects.0.width)))} height={("42.333351", rx_data.branch_map(|m| format!("{}", m.naive_rects.0.height)))} x={("111.125", rx_data.branch_map(|m| format!("{}", m.naive_rects.0.x)))} y={("37.041649", rx_data.branch_map(|m| format!("{}", m.naive_rects.0.y)))} xmlns=ns/>
-                    <rect id="ucb-2" style="fill:#ff5f59;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.naive_rects.1.width)))} height={("42.333355", rx_data.branch_map(|m| format!("{}", m.naive_rects.1.height)))} x={("111.125", rx_data.branch_map(|m| format!("{}", m.naive_rects.1.x)))} y={("79.375", rx_data.branch_map(|m| format!("{}", m.naive_rects.1.y)))} xmlns=ns/>
-                    <rect id="ucb-3" style="fill:#00b059;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.naive_rects.2.width)))} height={("21.166685", rx_data.branch_map(|m| format!("{}", m.naive_rects.2.height)))} x={("111.125", rx_data.branch_map(|m| format!("{}", m.naive_rects.2.x)))} y={("121.70831", rx_data.branch_map(|m| format!("{}", m.naive_rects.2.y)))} xmlns=ns/>
-                    <rect id="naive-1" style="fill:#009dff;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.ucb_rects.0.width)))} height={("42.333351", rx_data.branch_map(|m| format!("{}", m.ucb_rects.0.height)))} x={("68.791664", rx_data.branch_map(|m| format!("{}", m.ucb_rects.0.x)))} y={("37.041649", rx_data.branch_map(|m| format!("{}", m.ucb_rects.0.y)))} xmlns=ns/>
-                    <rect id="naive-2" style="fill:#ff5f59;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.ucb_rects.1.width)))} height={("42.333355", rx_data.branch_map(|m| format!("{}", m.ucb_rects.1.height)))} x={("68.791664", rx_data.branch_map(|m| format!("{}", m.ucb_rects.1.x)))} y={("79.375", rx_data.branch_map(|m| format!("{}", m.ucb_rects.1.y)))} xmlns=ns/>
-                    <rect id="naive-3" style="fill:#00b059;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.ucb_rects.2.width)))} height={("21.166685", rx_data.branch_map(|m| format!("{}", m.ucb_rects.2.height)))} x={("68.791664", rx_data.branch_map(|m| format!("{}", m.ucb_rects.2.x)))} y={("121.70831", rx_data.branch_map(|m| format!("{}", m.ucb_rects.2.y)))} xmlns=ns/>
-                    <rect id="thompson-1" style="fill:#009dff;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.player_rects.0.width)))} height={("42.333351", rx_data.branch_map(|m| format!("{}", m.player_rects.0.height)))} x={("26.458332", rx_data.branch_map(|m| format!("{}", m.player_rects.0.x)))} y={("37.041649", rx_data.branch_map(|m| format!("{}", m.player_rects.0.y)))} xmlns=ns/>
-                    <rect id="thompson-2" style="fill:#ff5f59;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.player_rects.1.width)))} height={("42.333355", rx_data.branch_map(|m| format!("{}", m.player_rects.1.height)))} x={("26.458332", rx_data.branch_map(|m| format!("{}", m.player_rects.1.x)))} y={("79.375", rx_data.branch_map(|m| format!("{}", m.player_rects.1.y)))} xmlns=ns/>
-                    <rect id="thompson-3" style="fill:#00b059;" width={("26.458334", rx_data.branch_map(|m| format!("{}", m.player_rects.2.width)))} height={("21.166685", rx_data.branch_map(|m| format!("{}", m.player_rects.2.height)))} x={("26.458332", rx_data.branch_map(|m| format!("{}", m.player_rects.2.x)))} y={("121.70831", rx_data.branch_map(|m| format!("{}", m.player_rects.2.y)))} xmlns=ns/>
                     // ? the text for the counts
                     <text xml:space="preserve" style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x="39.6875" y="158.75" id="text1076" xmlns=ns><tspan x="39.6875" y="158.75" style="stroke-width:0.264583" id="tspan1078" xmlns=ns>{("THOMPSON COUNT", rx_data.branch_map(|m| format!("{}/{}", m.thompson_counts.0,  m.thompson_counts.0 + m.thompson_counts.1)))}</tspan></text>
                     <text xml:space="preserve" style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x="82.020836" y="158.75" id="text1076-9" xmlns=ns><tspan x="82.020836" y="158.75" style="stroke-width:0.264583" id="tspan1078-4" xmlns=ns>{("NAIVE COUNT", rx_data.branch_map(|m| format!("{}/{}", m.naive_counts.0,  m.naive_counts.0 + m.naive_counts.1)))}</tspan></text>
                     <text xml:space="preserve" style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x="124.56371" y="158.74974" id="text1076-5" xmlns=ns><tspan x="124.56371" y="158.74974" style="stroke-width:0.264583" id="tspan1078-9" xmlns=ns>{("UCB COUNT", rx_data.branch_map(|m| format!("{}/{}", m.ucb_counts.0,  m.ucb_counts.0 + m.ucb_counts.1)))}</tspan></text>
                     <text xml:space="preserve" style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x="166.5509" y="158.74974" id="text1076-9-3" xmlns=ns><tspan  x="166.5509" y="158.74974" style="stroke-width:0.264583" id="tspan1078-4-9" xmlns=ns>{("PLAYER COUNT", rx_data.branch_map(|m| format!("{}/{}", m.player_counts.0,  m.player_counts.0 + m.player_counts.1)))}</tspan></text>
+                    // ? the naive panel's axes and what naive is doing
+                    <path fill="none" stroke="rgba(0,0,0,255)" stroke-width="0.264583" d={naive_axes()} xmlns=ns/>
+                    <text style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x={NAIVE_PANEL.x.to_string()} y="293" xmlns=ns>{"0"}</text>
+                    <text style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x={(NAIVE_PANEL.x + NAIVE_PANEL.width).to_string()} y="293" xmlns=ns>{"1"}</text>
+                    <text style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-anchor:start;stroke-width:0.264583" x={NAIVE_PANEL.x.to_string()} y="247" xmlns=ns>{("NAIVE STATUS", rx_data.branch_map(|m| naive_status(m.naive_explored, m.naive_committed)))}</text>
                     // ? the title for the player name
                     <text style="font-size:4.5861px;line-height:1.25;font-family:sans-serif;text-align:center;text-anchor:middle;stroke-width:0.264583" x="166.6875" y="23.8125" xmlns=ns><tspan id="tspan1192" x="166.6875" y="23.8125" style="stroke-width:0.264583" xmlns=ns>{("Player Name", rx_name)}</tspan></text>
                 </svg>
+                // ? the time series of the session, with a cursor at the current step
+                <div patch:children=rx_charts>
+                    <svg xmlns=ns></svg>
+                </div>
                 <div>
                     <p><button on:click=tx_backward type="button">{"<-"}</button> <span>{(" 0/",rx_index)}</span>{("0 ",rx_length)} <button on:click=tx_forward type="button">{"->"}</button></p>
                 </div>
                 <br></br>
                 <input on:change=tx_input type="file" accept=".cbor">{"Paste data here"}</input>
+                <button on:click=tx_load type="button">{"Load the current round"}</button>
+                // ? the rendered players, best first when loaded from the server
+                <span patch:children=rx_picker>
+                    <select></select>
+                </span>
             </div>
         )
     }
 }
 
+/// The left and bottom edges of the naive panel, with a tick at 1
+fn naive_axes() -> String {
+    let (left, top) = (NAIVE_PANEL.x, NAIVE_PANEL.y);
+    let (right, bottom) = (left + NAIVE_PANEL.width, top + NAIVE_PANEL.height);
+    format!(
+        "M {} {} L {} {} L {} {} M {} {} L {} {}",
+        left, top, left, bottom, right, bottom, right, bottom, right, bottom + 2.64584
+    )
+}
+
+/// A choice of the rendered players, the first chosen
+#[allow(unused_braces)]
+fn player_picker(names: &[String], tx: &Transmitter<ViewerIn>) -> ViewBuilder<HtmlElement> {
+    let tx_player = tx.contra_map(|e: &Event| {
+        let index = e
+            .target()
+            .expect("Must have a target for event")
+            .unchecked_ref::<HtmlSelectElement>()
+            .selected_index();
+        ViewerIn::Player(index.max(0) as usize)
+    });
+    let mut select = builder!(<select on:change=tx_player></select>);
+    for name in names {
+        select.with(builder!(<option>{name.clone()}</option>));
+    }
+    select
+}
+
+/// The curves, intervals and stacked boxes for every coin, in the given css colors, following the
+/// current step
+#[allow(unused_braces)]
+fn coin_shapes(colors: &[String], rx_data: &Receiver<Box<RenderedState>>) -> ViewBuilder<HtmlElement> {
+    let ns = "http://www.w3.org/2000/svg";
+    let strategies: [fn(&RenderedState) -> &Vec<Rectangle>; 4] = [
+        |m| &m.thompson_rects,
+        |m| &m.naive_rects,
+        |m| &m.ucb_rects,
+        |m| &m.player_rects,
+    ];
+    let mut group = builder!(<g xmlns=ns></g>);
+    for (coin, color) in colors.iter().cloned().enumerate() {
+        let stroke = format!("stroke:{};fill:none;", color);
+        let fill = format!("fill:{};", color);
+        let thompson = rx_data.branch_map(move |m| m.thompson_paths.get(coin).cloned().unwrap_or_default());
+        let ucb = rx_data.branch_map(move |m| m.ucb_paths.get(coin).cloned().unwrap_or_default());
+        group.with(builder!(
+            <path d={("", thompson)} class="graph" style={stroke.clone()} stroke-width="0.02" transform="matrix(52.92 0.0 0.0 -26.46 31.76225 232.815)" xmlns=ns/>
+        ));
+        group.with(builder!(
+            <path d={("", ucb)} class="graph" style={stroke} stroke-width="0.02" transform="matrix(26.46 0.0 0.0 -52.92 116.445 232.815)" xmlns=ns/>
+        ));
+        // naive's estimate of the coin, outlined once naive settles on it and faded if it does not
+        let bar = move |f: fn(&Rectangle) -> f64| {
+            rx_data.branch_map(move |m| m.naive_means.get(coin).map_or(0.0, f).to_string())
+        };
+        let bar_style = rx_data.branch_map(move |m| match m.naive_committed {
+            Some(chosen) if chosen == coin => format!("fill:{};stroke:black;stroke-width:0.8;", color),
+            Some(_) => format!("fill:{};fill-opacity:0.35;stroke:none;", color),
+            None => format!("fill:{};stroke:none;", color),
+        });
+        group.with(builder!(
+            <rect style={("", bar_style)} width={("0", bar(|r| r.width))} height={("0", bar(|r| r.height))} x={("0", bar(|r| r.x))} y={("0", bar(|r| r.y))} xmlns=ns/>
+        ));
+        for rects in strategies.iter().copied() {
+            let attribute = move |f: fn(&Rectangle) -> f64| {
+                rx_data.branch_map(move |m| rects(m).get(coin).map_or(0.0, f).to_string())
+            };
+            group.with(builder!(
+                <rect style={fill.clone()} width={("0", attribute(|r| r.width))} height={("0", attribute(|r| r.height))} x={("0", attribute(|r| r.x))} y={("0", attribute(|r| r.y))} xmlns=ns/>
+            ));
+        }
+    }
+    group
+}
+
+/// The charts, below the page and as wide as it, with their cursors following the current step
+#[allow(unused_braces)]
+fn chart_view(charts: &[Chart], rx_data: &Receiver<Box<RenderedState>>) -> ViewBuilder<HtmlElement> {
+    let ns = "http://www.w3.org/2000/svg";
+    if charts.is_empty() {
+        return builder!(<svg width="0" height="0" xmlns=ns></svg>);
+    }
+    let top = charts
+        .iter()
+        .map(|chart| chart.rect.y - chart.font_size * 1.5)
+        .fold(f64::INFINITY, f64::min);
+    let (right, bottom) = charts
+        .iter()
+        .map(Chart::extent)
+        .fold((210.0_f64, top), |(right, bottom), (x, y)| (right.max(x), bottom.max(y)));
+    let view_box = format!("0 {} {} {}", top, right, bottom - top);
+    let mut svg = builder!(<svg style="width: 100%;" viewBox=view_box xmlns=ns></svg>);
+    for chart in charts.iter().cloned() {
+        svg.with(builder!(
+            <path fill="none" stroke="black" stroke-width="0.264583" d={svg_path(&chart.axes())} xmlns=ns/>
+        ));
+        for line in &chart.lines {
+            let style = format!("fill:none;stroke:{};stroke-width:0.5;stroke-linejoin:round;", css_color(line.color));
+            svg.with(builder!(<path style=style d={svg_path(&line.points)} xmlns=ns/>));
+        }
+        for label in chart.labels() {
+            let anchor = match label.align {
+                Align::Start => "start",
+                Align::Middle => "middle",
+                Align::End => "end",
+            };
+            let style = format!("font-size:{}px;font-family:sans-serif;text-anchor:{}", label.font_size, anchor);
+            svg.with(builder!(
+                <text style=style x={label.x.to_string()} y={label.y.to_string()} xmlns=ns>{label.text}</text>
+            ));
+        }
+        let cursor = rx_data.branch_map(move |m| svg_path(&chart.cursor(m.step)));
+        svg.with(builder!(
+            <path fill="none" stroke="grey" stroke-width="0.3" d={("", cursor)} xmlns=ns/>
+        ));
+    }
+    svg
+}
+
 #[wasm_bindgen]
 pub fn main(parent_id: Option<String>) -> Result<(), JsValue> {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
     console_log::init_with_level(Level::Trace).unwrap();
 
     let gizmo = Gizmo::from(Viewer {
-        data: RenderedStateContainer::default(),
+        data: Rendered::default(),
         index: 0,
+        player: 0,
     });
     let view = View::from(gizmo.view_builder());
+    gizmo.send(&ViewerIn::Load);
 
     if let Some(id) = parent_id {
         let parent = utils::document().get_element_by_id(&id).unwrap();